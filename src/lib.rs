@@ -0,0 +1,2415 @@
+//! Importing bank statements and aggregating them into groups, the core of the `spenben` CLI
+
+use ahash::*;
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{Datelike, NaiveDate};
+use csv::ReaderBuilder;
+use num_format::{parsing::ParseFormatted, Locale};
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use std::fmt::{Display, Error, Formatter, Write as _};
+use std::path::Path;
+use tracing::{debug, warn};
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+pub struct MonthYear {
+    pub month: u32,
+    pub year: i32,
+}
+
+impl Display for MonthYear {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(f, "{} {}", self.year, self.month)
+    }
+}
+
+impl MonthYear {
+    /// Formats the month with a chrono format like "%B %Y", with month names in the locale.
+    /// Falls back to "2024 3" if the format is invalid or needs more than a date, ie. "%H".
+    pub fn label(&self, format: &str, locale: chrono::Locale) -> String {
+        let mut label = String::new();
+        NaiveDate::from_ymd_opt(self.year, self.month, 1)
+            .filter(|date| write!(label, "{}", date.format_localized(format, locale)).is_ok())
+            .map_or_else(|| self.to_string(), |_| label)
+    }
+}
+
+impl PartialOrd for MonthYear {
+    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
+        Some(self.cmp(&rhs))
+    }
+}
+
+impl Ord for MonthYear {
+    fn cmp(&self, rhs: &Self) -> Ordering {
+        self.year
+            .cmp(&rhs.year)
+            .then_with(|| (self.month as u8).cmp(&(rhs.month as u8)))
+    }
+}
+
+impl From<NaiveDate> for MonthYear {
+    fn from(date: NaiveDate) -> Self {
+        Self {
+            month: date.month(),
+            year: date.year(),
+        }
+    }
+}
+
+/// Inclusive range of months, given as "2023-01..2023-06" or a whole year as "2023"
+#[derive(Clone, Copy)]
+pub struct MonthRange {
+    pub from: MonthYear,
+    pub to: MonthYear,
+}
+
+impl std::str::FromStr for MonthRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let month = |value: &str| {
+            let (year, month) = value
+                .trim()
+                .split_once('-')
+                .ok_or_else(|| format!("Expected a month like 2023-01, got '{value}'"))?;
+            let month = MonthYear {
+                year: year.parse().map_err(|_| format!("Invalid year '{year}'"))?,
+                month: month
+                    .parse()
+                    .map_err(|_| format!("Invalid month '{month}'"))?,
+            };
+            if !(1..=12).contains(&month.month) {
+                return Err(format!("Invalid month '{value}'"));
+            }
+            Ok(month)
+        };
+        let range = match value.split_once("..") {
+            Some((from, to)) => Self {
+                from: month(from)?,
+                to: month(to)?,
+            },
+            None => {
+                let year = value
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("Expected a year or a range of months, got '{value}'"))?;
+                Self {
+                    from: MonthYear { month: 1, year },
+                    to: MonthYear { month: 12, year },
+                }
+            }
+        };
+        if range.from > range.to {
+            return Err(format!("The range '{value}' ends before it starts"));
+        }
+        Ok(range)
+    }
+}
+
+/// Inclusive range of days, given as "2024-07-01:2024-07-21"
+#[derive(Clone, Copy)]
+pub struct DateRange {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl DateRange {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        (self.from..=self.to).contains(&date)
+    }
+}
+
+impl std::str::FromStr for DateRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let date = |value: &str| {
+            NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+                .map_err(|_| format!("Expected a date like 2024-07-01, got '{value}'"))
+        };
+        let (from, to) = value
+            .split_once(':')
+            .ok_or_else(|| format!("Expected FROM:TO, got '{value}'"))?;
+        let range = Self {
+            from: date(from)?,
+            to: date(to)?,
+        };
+        if range.from > range.to {
+            return Err(format!("The range '{value}' ends before it starts"));
+        }
+        Ok(range)
+    }
+}
+
+impl Display for MonthRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        write!(
+            f,
+            "{}-{:02}..{}-{:02}",
+            self.from.year, self.from.month, self.to.year, self.to.month
+        )
+    }
+}
+
+/// Additional analyses of the records
+#[derive(Default)]
+pub struct AnalysisOptions {
+    /// List the most frequent words in descriptions, weighted by amount
+    pub keywords: bool,
+    /// Count and sum up records by size, separately for income and expenses
+    pub histogram: bool,
+    /// Boundaries between the size bands of the histogram
+    pub bands: Vec<f64>,
+    /// List the given number of largest records of each month
+    pub top_transactions: usize,
+    /// Collect the records larger than this absolute amount
+    pub highlight_over: Option<f64>,
+    /// List the given number of parties with the most spending, regardless of the groups, ie. to
+    /// find parties without a rule
+    pub top_parties: usize,
+    /// Leave the months without records of a group out of its volatility, instead of counting
+    /// them as zero. Zero months make irregular groups appear more volatile.
+    pub volatility_active_months: bool,
+    /// Warn about at most this many groups without a rule by name, 20 if not given. The others
+    /// are only counted.
+    pub unmatched_warnings: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportConfig {
+    pub skip: Option<usize>,
+    pub date_format: String,
+    pub number_locale: Option<String>,
+    /// Shift all dates by this number of days. A workaround for banks posting transactions
+    /// on the wrong day (ie. in UTC instead of local time), moving them into the wrong month.
+    pub date_offset: Option<i64>,
+    /// Field ("party1", "party2" or "description") to fill a party from, if its own column is
+    /// empty in a row. The party column always takes precedence.
+    pub party_fallback: Option<String>,
+    /// Fields to fill, keyed by a regular expression matching the column header. Instead of
+    /// "amount", "amount_int" and "amount_cents" can be mapped to read amounts split into two
+    /// columns.
+    pub map: BTreeMap<String, String>,
+    /// What to do with rows with fewer columns than needed for the mapped fields. Additional
+    /// columns are always ignored.
+    #[serde(default)]
+    pub short_rows: ShortRows,
+    /// Currency symbols to remove from amounts, before or after the number, ie. ["€", "EUR"]
+    #[serde(default)]
+    pub currency_symbols: Vec<String>,
+    /// What to do with rows with an empty amount, ie. separator rows within the file
+    #[serde(default)]
+    pub empty_amount: EmptyAmount,
+    /// Transformations applied to the values of a field in order, keyed by field name
+    #[serde(default)]
+    pub transform: BTreeMap<String, Vec<TransformConfig>>,
+    /// Name of an OS keyring entry (service "spenben") holding a bearer token for files given as
+    /// URL. An "Authorization" header given by `--header` takes precedence.
+    pub keyring: Option<String>,
+    /// Parse amounts like "1.2E3" in scientific notation, if they can't be parsed according to the
+    /// locale. Some exports write them by mistake.
+    #[serde(default)]
+    pub scientific_amounts: bool,
+    /// Parse amounts like "1.2k" or "3M" in thousands or millions, with the number before the
+    /// suffix in the locale. Some condensed exports write them this way.
+    #[serde(default)]
+    pub suffixed_amounts: bool,
+    /// Don't warn about the columns found not matching the mapped fields, for wide exports of
+    /// which only a few columns are of interest. Fields without a matching column are still
+    /// warned about, and `--strict-headers` still applies.
+    #[serde(default)]
+    pub ignore_unmapped: bool,
+    /// Decimal places of the currency. With 0, for currencies without a minor unit like JPY,
+    /// amounts are read as whole numbers, taking any separator for grouping. Other values keep
+    /// the usual parsing.
+    pub minor_units: Option<u32>,
+    /// What to show for records without a description, in exports and the report
+    #[serde(default)]
+    pub empty_description: EmptyDescription,
+    /// Encoding of the file, ie. "windows-1252" or "utf-16le", UTF-8 by default. A byte order
+    /// mark at the start of the file takes precedence, so UTF-16 files with one work either way.
+    pub encoding: Option<String>,
+    /// Keep line breaks within quoted fields. By default, the lines are joined by spaces, so rules
+    /// match descriptions and parties spanning several lines like any other.
+    #[serde(default)]
+    pub keep_newlines: bool,
+    /// Keep the line of each record in the file, ie. to find it again from the JSON Lines export
+    #[serde(default)]
+    pub row_numbers: bool,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyDescription {
+    /// Leave it empty
+    #[default]
+    Keep,
+    /// The payee of expenses, the payer of income
+    Party,
+    /// The given text, ie. { text = "(none)" }
+    Text(String),
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortRows {
+    #[default]
+    Error,
+    /// Skip the row with a warning
+    Skip,
+    /// Treat missing columns as empty
+    Pad,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyAmount {
+    /// Fail the import
+    Error,
+    /// Treat the amount as zero
+    Zero,
+    #[default]
+    Skip,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransformConfig {
+    /// Replace all matches of the regular expression
+    Replace { pattern: String, with: String },
+    /// Multiply the parsed amount, only valid for the amount field
+    Multiply(f64),
+    /// Remove leading and trailing whitespace
+    Trim,
+}
+
+enum Transform {
+    Replace(Regex, String),
+    Multiply(f64),
+    Trim,
+}
+
+impl Transform {
+    fn new(field: &str, config: &TransformConfig) -> Result<Self> {
+        Ok(match config {
+            TransformConfig::Replace { pattern, with } => {
+                Transform::Replace(Regex::new(pattern)?, with.clone())
+            }
+            TransformConfig::Multiply(_) if field != "amount" => {
+                bail!("Multiply can only transform the amount, not '{}'", field)
+            }
+            TransformConfig::Multiply(factor) => Transform::Multiply(*factor),
+            TransformConfig::Trim => Transform::Trim,
+        })
+    }
+
+    fn apply_text<'a>(&self, value: Cow<'a, str>) -> Cow<'a, str> {
+        match self {
+            Transform::Replace(regex, with) => {
+                Cow::Owned(regex.replace_all(&value, with.as_str()).into_owned())
+            }
+            Transform::Trim => Cow::Owned(value.trim().to_string()),
+            Transform::Multiply(_) => value,
+        }
+    }
+
+    fn apply_amount(&self, amount: f64) -> f64 {
+        match self {
+            Transform::Multiply(factor) => amount * factor,
+            _ => amount,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+pub struct GroupConfig {
+    pub parties: BTreeMap<String, PartyRule>,
+    /// How to group records no rule matched, by default by the whole party
+    #[serde(default)]
+    pub unmatched: Unmatched,
+    /// Number of groups listed per month, 20 by default
+    pub monthly_top: Option<usize>,
+    /// Sum up groups no rule matched into a single "Other" line per month, instead of letting
+    /// them compete with the named groups for the top spots. A group named "Other" is added to
+    /// that line
+    #[serde(default)]
+    pub monthly_other: bool,
+    /// Additional settings per group, keyed by group name
+    #[serde(default)]
+    pub settings: BTreeMap<String, GroupSettings>,
+    /// Match parties looking like an IBAN without spaces and in upper case, instead of in lower
+    /// case like names. Rules can then be written like "DE89370400440532013000".
+    #[serde(default)]
+    pub normalize_iban: bool,
+    /// Whether records with an amount of exactly zero, ie. adjustments, count as income (the
+    /// default) or expense when picking the party to match, matching the sign of rules and in the
+    /// histogram
+    #[serde(default)]
+    pub zero_amounts: ZeroAmounts,
+    /// Split every group into income and expenses, ie. "Shop (in)" for refunds and "Shop (out)"
+    /// for purchases. Settings have to be given for the split names.
+    #[serde(default)]
+    pub split_by_direction: bool,
+    /// Texts the rules are matched against in order, until one matches, ie. ["party",
+    /// "description", "memo"]. Without any, only the party is matched. Records no rule matched are
+    /// grouped by their party like configured by `unmatched`.
+    #[serde(default)]
+    pub sources: Vec<Source>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// The payee of expenses, the payer of income
+    Party,
+    Description,
+    Memo,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ZeroAmounts {
+    /// Matched by the payer in `party1`, like positive amounts
+    #[default]
+    Income,
+    /// Matched by the payee in `party2`, like negative amounts
+    Expense,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Unmatched {
+    #[default]
+    Party,
+    /// Only the first word of the party, ie. "amazon" for "amazon eu s.a.r.l."
+    FirstWord,
+    /// Only the given number of leading characters of the party
+    Prefix(usize),
+}
+
+impl Unmatched {
+    fn group(self, party: &str) -> String {
+        match self {
+            Unmatched::Party => party.to_string(),
+            Unmatched::FirstWord => party.split_whitespace().next().unwrap_or("").to_string(),
+            Unmatched::Prefix(len) => party
+                .chars()
+                .take(len)
+                .collect::<String>()
+                .trim()
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct GroupSettings {
+    /// Accumulate the absolute amount, ie. the gross volume instead of the net sum.
+    /// Such a group will no longer correctly contribute to the net total.
+    #[serde(default)]
+    pub absolute: bool,
+    /// Monthly budget for spending in this group
+    pub budget: Option<f64>,
+    /// Carry unused budget (or overspending) forward into the next month
+    #[serde(default)]
+    pub rollover: bool,
+    /// Free text shown next to the group, ie. "review quarterly"
+    pub notes: Option<String>,
+    /// Counts the group as income or expense in the headline totals regardless of the sign of
+    /// its sum, or as neither
+    pub kind: Option<Kind>,
+    /// How the records are combined, the sum by default. Other than for sums, the per month
+    /// figures, budgets and the totals and net the group contributes to are meaningless.
+    #[serde(default)]
+    pub agg: Agg,
+    /// Leave the group out of the headline totals and the net, ie. for reimbursable expenses.
+    /// It is still listed, so the groups no longer add up to the net.
+    #[serde(default)]
+    pub exclude_from_net: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Income,
+    Expense,
+    Neutral,
+}
+
+/// Either just the name of the group, or a table with additional restrictions
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum PartyRule {
+    Group(String),
+    Detailed {
+        group: String,
+        #[serde(default)]
+        sign: Sign,
+        /// Inclusive lower bound of the absolute amount
+        min: Option<f64>,
+        /// Exclusive upper bound of the absolute amount
+        max: Option<f64>,
+        /// Name the group after the text the pattern matched (in lower case) instead, ie. to
+        /// discover the merchants behind a payment provider. `group` is used for empty matches.
+        #[serde(default)]
+        use_match: bool,
+    },
+}
+
+/// Restricts a rule to records with the given sign of amount
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Sign {
+    Positive,
+    Negative,
+    #[default]
+    Any,
+}
+
+impl Sign {
+    fn matches(self, expense: bool) -> bool {
+        match self {
+            Sign::Positive => !expense,
+            Sign::Negative => expense,
+            Sign::Any => true,
+        }
+    }
+}
+
+/// Input specification and group mapping in one file
+#[derive(Debug, Deserialize)]
+pub struct CombinedConfig {
+    pub import: ImportConfig,
+    #[serde(default)]
+    pub groups: GroupConfig,
+}
+
+const CSV_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Format used for internal database (not yet implemented)
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Record<'r> {
+    #[serde(serialize_with = "ser_date", deserialize_with = "deser_date")]
+    pub date: NaiveDate,
+    pub party1: &'r str,
+    pub party2: &'r str,
+    pub description: &'r str,
+    /// Secondary, usually longer description, empty if not mapped
+    #[serde(default)]
+    pub memo: &'r str,
+    pub amount: f64,
+    /// Currency of the amount, empty if unknown
+    pub currency: &'r str,
+    /// Unique id of the transaction, if the export has one
+    #[serde(borrow)]
+    pub txn_id: Option<&'r str>,
+    /// Line of the record in the imported file, if enabled by `row_numbers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row: Option<u64>,
+}
+
+fn ser_date<S>(date: &NaiveDate, s: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    s.serialize_str(&date.format(&CSV_DATE_FORMAT).to_string())
+}
+
+fn deser_date<'de, D>(d: D) -> Result<NaiveDate, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct FieldVisitor;
+    use serde::de;
+    use std::fmt;
+    impl<'de> de::Visitor<'de> for FieldVisitor {
+        type Value = NaiveDate;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("YYYY-MM-DD")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<NaiveDate, E>
+        where
+            E: de::Error,
+        {
+            NaiveDate::parse_from_str(value, &CSV_DATE_FORMAT).map_err(|e| de::Error::custom(e))
+        }
+    }
+    d.deserialize_str(FieldVisitor)
+}
+
+/// Removes any of the symbols and whitespace from the start and end of the value
+fn strip_symbols<'a>(value: &'a str, symbols: &[String]) -> &'a str {
+    let mut value = value.trim();
+    for symbol in symbols {
+        value = value
+            .strip_prefix(symbol.as_str())
+            .unwrap_or(value)
+            .trim_start();
+        value = value
+            .strip_suffix(symbol.as_str())
+            .unwrap_or(value)
+            .trim_end();
+    }
+    value
+}
+
+/// Parses an amount formatted according to the locale, anything after the fraction separated by
+/// a non-breaking space (ie. a currency) is ignored. The given currency symbols are removed before
+/// or after the number. Negative amounts may also be written in parentheses accounting style,
+/// ie. "(1.234,56)".
+pub fn parse_amount(value: &str, locale: &Locale, symbols: &[String]) -> Result<f64> {
+    let value = strip_symbols(value, symbols);
+    let (parenthesized, value) = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+        Some(inner) => (true, strip_symbols(inner, symbols)),
+        None => (false, value),
+    };
+    let (int, fract) = value.split_once(locale.decimal()).unwrap_or((value, "0"));
+    // Some exports write an explicit positive sign
+    let int = int.trim();
+    let int = int.strip_prefix('+').unwrap_or(int).trim_start();
+    let fract = fract.split_once('\u{a0}').map(|(r, _)| r).unwrap_or(fract);
+    let mut result = int.parse_formatted::<_, i64>(locale)?.abs() as f64;
+    // The length of the fraction gives its scale, so leading zeros count ("05" is 0.05). A
+    // trailing separator without digits ("12,") is no fraction at all.
+    if !fract.is_empty() {
+        result += fract.parse::<u64>()? as f64 * 10.0_f64.powf(-(fract.len() as f64));
+    }
+    // Checking the text, as the sign of "-0,50" would be lost in the integer part
+    if int.starts_with(locale.minus_sign()) != parenthesized {
+        result = -result;
+    }
+    Ok(result)
+}
+
+/// Joins the lines of a field by single spaces, leaving out empty ones
+fn join_lines(value: &str) -> String {
+    value
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits amounts like "1.2k" or "3M" into the amount before the suffix and its factor
+fn split_suffix(value: &str) -> Option<(&str, f64)> {
+    let value = value.trim();
+    if let Some(amount) = value.strip_suffix(['k', 'K']) {
+        Some((amount, 1e3))
+    } else {
+        value.strip_suffix('M').map(|amount| (amount, 1e6))
+    }
+}
+
+/// Parses an amount without fractional part, ie. "1.234" as 1234 regardless of the locale
+/// Parses an amount without fractional part, ie. "1.234" as 1234 regardless of the locale. Any
+/// separator is taken for grouping, so one not followed by three digits starts a decimal part,
+/// which is an error instead of being read as part of the amount.
+pub fn parse_whole_amount(value: &str, locale: &Locale, symbols: &[String]) -> Result<f64> {
+    let is_separator = |c: char| locale.decimal().contains(c) || locale.separator().contains(c);
+    for (i, c) in value.char_indices().filter(|(_, c)| is_separator(*c)) {
+        let digits = value[i + c.len_utf8()..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits != 3 {
+            bail!("'{value}' has a decimal part, but the currency has no minor unit");
+        }
+    }
+    let value: String = value.chars().filter(|c| !is_separator(*c)).collect();
+    parse_amount(&value, locale, symbols)
+}
+
+/// The line of a CSV record, for messages
+fn line(position: Option<&csv::Position>) -> String {
+    position.map_or_else(
+        || "an unknown line".to_string(),
+        |position| format!("line {}", position.line()),
+    )
+}
+
+pub fn import(
+    mut input: impl std::io::Read,
+    config: &ImportConfig,
+    strict_headers: bool,
+    mut taker: impl FnMut(Record) -> (),
+) -> Result<()> {
+    // The CSV reader works on bytes, so UTF-16 in particular has to be decoded upfront
+    let encoding = match &config.encoding {
+        Some(label) => encoding_rs::Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| anyhow!("Unknown encoding '{}'", label))?,
+        None => encoding_rs::UTF_8,
+    };
+    let mut content = vec![];
+    input.read_to_end(&mut content)?;
+    let (content, encoding, malformed) = encoding.decode(&content);
+    if malformed {
+        warn!(
+            "The file contains invalid characters for {}, is the encoding right?",
+            encoding.name()
+        );
+    }
+    let date_format = &config.date_format;
+    let date_offset = config.date_offset.unwrap_or(0);
+    // Larger offsets move any date out of range, and would overflow the duration
+    if date_offset.abs() > (NaiveDate::MAX - NaiveDate::MIN).num_days() {
+        bail!("The date_offset of {} days is out of range", date_offset);
+    }
+    let date_offset = chrono::Duration::days(date_offset);
+    let number_locale = config
+        .number_locale
+        .as_ref()
+        .map(|locale| Locale::from_name(locale))
+        .transpose()?
+        .unwrap_or(Locale::en);
+
+    let parse: fn(&str, &Locale, &[String]) -> Result<f64> = if config.minor_units == Some(0) {
+        parse_whole_amount
+    } else {
+        parse_amount
+    };
+
+    let rdr = ReaderBuilder::new()
+        .delimiter(b';')
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(content.as_bytes());
+    let records = rdr.into_byte_records();
+    let mut records = records.skip(config.skip.unwrap_or(0));
+    let header = records.next().ok_or(anyhow!(""))??;
+    let field_matchers: Vec<_> = config
+        .map
+        .iter()
+        .flat_map(|(regex, field)| Regex::new(regex).map(|regex| (regex, field)))
+        .collect();
+    let headers: Vec<_> = header
+        .iter()
+        .enumerate()
+        .flat_map(|(i, hdr)| {
+            field_matchers
+                .iter()
+                .find(|(regex, _)| regex.is_match(&String::from_utf8_lossy(hdr)))
+                .map(|(_, field)| (i, field))
+        })
+        .collect();
+    // A field whose pattern is invalid, or whose column was taken by another pattern, is missing
+    // just as well
+    let missing: Vec<_> = config
+        .map
+        .iter()
+        .filter(|(_, field)| {
+            !headers
+                .iter()
+                .any(|(_, assigned)| assigned.as_str() == field.as_str())
+        })
+        .map(|(regex, field)| format!("{field} ('{regex}')"))
+        .collect();
+    if strict_headers && !missing.is_empty() {
+        bail!(
+            "Configured fields without a matching column: {}",
+            missing.join(", ")
+        );
+    }
+    // Fields without a column are worth a warning even with `ignore_unmapped`
+    if !missing.is_empty() || (headers.len() != config.map.len() && !config.ignore_unmapped) {
+        warn!(
+            "Headers configured: {:?}, headers actually found: {:?}",
+            config.map.keys().collect::<Vec<_>>(),
+            headers
+        );
+    }
+    if !config.ignore_unmapped {
+        debug!("{headers:?}");
+    }
+    if let Some(fallback) = &config.party_fallback {
+        if !["party1", "party2", "description"].contains(&fallback.as_str()) {
+            bail!(
+                "Party fallback '{}' is not one of party1, party2 or description",
+                fallback
+            );
+        }
+    }
+    let split_amount =
+        ["amount_int", "amount_cents"].map(|field| config.map.values().any(|f| f == field));
+    match split_amount {
+        [true, false] | [false, true] => {
+            bail!("The fields amount_int and amount_cents have to be mapped together")
+        }
+        [true, true] if config.map.values().any(|field| field == "amount") => {
+            bail!("Either amount or amount_int and amount_cents can be mapped, not both")
+        }
+        _ => (),
+    }
+    let transforms = config
+        .transform
+        .iter()
+        .map(|(field, transforms)| {
+            let transforms = transforms
+                .iter()
+                .map(|transform| Transform::new(field, transform))
+                .collect::<Result<Vec<_>>>()?;
+            Ok((field.as_str(), transforms))
+        })
+        .collect::<Result<AHashMap<_, _>>>()?;
+    let columns_needed = headers
+        .iter()
+        .map(|(index, _)| index + 1)
+        .max()
+        .unwrap_or(0);
+    let mut short_rows = 0;
+    let mut empty_amounts = 0;
+    'records: for result in records {
+        let result = result?;
+        if result.len() < columns_needed {
+            match config.short_rows {
+                ShortRows::Error => bail!("Not enough data columns at {}", line(result.position())),
+                ShortRows::Skip => {
+                    warn!(
+                        "Skipping row with too few columns at {}",
+                        line(result.position())
+                    );
+                    short_rows += 1;
+                    continue;
+                }
+                ShortRows::Pad => short_rows += 1,
+            }
+        }
+        let mut date = None;
+        let mut party1 = None;
+        let mut party2 = None;
+        let mut amount = None;
+        let mut amount_int = None;
+        let mut amount_cents = None;
+        let mut description = "".to_string();
+        let mut txn_id = None;
+        let mut memo = "".to_string();
+        let mut currency = "".to_string();
+        for (index, field) in headers.iter() {
+            let mut value = encoding_rs::UTF_8
+                .decode_without_bom_handling(result.get(*index).unwrap_or(b""))
+                .0;
+            if !config.keep_newlines && value.contains(['\n', '\r']) {
+                value = Cow::Owned(join_lines(&value));
+            }
+            let transforms = transforms.get(field.as_str()).map_or(&[][..], |t| &t[..]);
+            for transform in transforms {
+                value = transform.apply_text(value);
+            }
+            match field.as_str() {
+                "date" => {
+                    date = Some(
+                        NaiveDate::parse_from_str(&value, &date_format).with_context(|| {
+                            format!(
+                                "Parsing '{}' at {} - is the format '{:?}' correct?",
+                                value,
+                                line(result.position()),
+                                date_format
+                            )
+                        })?,
+                    )
+                }
+                "party1" => party1 = Some(value.to_string()),
+                "party2" => party2 = Some(value.to_string()),
+                "amount" if value.trim().is_empty() => match config.empty_amount {
+                    EmptyAmount::Error => bail!("Empty amount at {}", line(result.position())),
+                    EmptyAmount::Zero => {
+                        empty_amounts += 1;
+                        amount = Some(0.0);
+                    }
+                    EmptyAmount::Skip => {
+                        empty_amounts += 1;
+                        continue 'records;
+                    }
+                },
+                "amount" => {
+                    // Detected before parsing, as a locale may read "3M" as 3, ignoring the suffix
+                    let suffixed = config
+                        .suffixed_amounts
+                        .then(|| split_suffix(&value))
+                        .flatten();
+                    let parsed = if let Some((suffixed, factor)) = suffixed {
+                        let parsed = parse(suffixed, &number_locale, &config.currency_symbols)
+                            .with_context(|| {
+                                format!("Parsing '{}' at {}", value, line(result.position()))
+                            })?
+                            * factor;
+                        warn!(
+                            "Parsed '{}' at {} with a suffix as {}",
+                            value,
+                            line(result.position()),
+                            parsed
+                        );
+                        parsed
+                    } else {
+                        match parse(&value, &number_locale, &config.currency_symbols) {
+                            Ok(parsed) => parsed,
+                            Err(e) => match config
+                                .scientific_amounts
+                                .then(|| value.trim().parse::<f64>().ok())
+                                .flatten()
+                            {
+                                Some(parsed) => {
+                                    warn!(
+                                        "Parsed '{}' at {} in scientific notation",
+                                        value,
+                                        line(result.position())
+                                    );
+                                    parsed
+                                }
+                                None => {
+                                    return Err(e).with_context(|| {
+                                        format!(
+                                            "Parsing '{}' at {}",
+                                            value,
+                                            line(result.position())
+                                        )
+                                    })
+                                }
+                            },
+                        }
+                    };
+                    amount = Some(
+                        transforms
+                            .iter()
+                            .fold(parsed, |amount, transform| transform.apply_amount(amount)),
+                    )
+                }
+                // Integer part and cents of the amount in separate columns
+                "amount_int" => amount_int = Some(value.to_string()),
+                "amount_cents" => amount_cents = Some(value.to_string()),
+                "description" => description = value.to_string(),
+                "txn_id" => txn_id = Some(value.to_string()),
+                "memo" => memo = value.to_string(),
+                "currency" => currency = value.trim().to_string(),
+                "party" => {
+                    party1 = Some(value.to_string());
+                    party2 = Some(value.to_string());
+                }
+                _ => unreachable!("Field '{}' does not exist", field),
+            }
+        }
+        if let (Some(int), Some(cents)) = (&amount_int, &amount_cents) {
+            let int = parse(int, &number_locale, &config.currency_symbols)
+                .with_context(|| format!("Parsing '{}' at {}", int, line(result.position())))?;
+            let cents = match cents.trim() {
+                "" => 0,
+                cents => cents
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|c| *c < 100)
+                    .ok_or_else(|| {
+                        anyhow!("Invalid cents '{}' at {}", cents, line(result.position()))
+                    })?,
+            };
+            // The sign is only given with the integer part, even if it is zero ("-0")
+            amount = Some((int.abs() + cents as f64 / 100.0).copysign(int));
+        }
+        if let Some(fallback) = &config.party_fallback {
+            let fallback = match fallback.as_str() {
+                "party1" => party1.clone(),
+                "party2" => party2.clone(),
+                _ => Some(description.clone()),
+            };
+            for party in [&mut party1, &mut party2] {
+                if party.as_deref().is_none_or(|party| party.trim().is_empty()) {
+                    *party = fallback.clone();
+                }
+            }
+        }
+        if description.trim().is_empty() {
+            match &config.empty_description {
+                EmptyDescription::Keep => (),
+                EmptyDescription::Party => {
+                    let party = if amount.is_some_and(|amount| amount < 0.0) {
+                        &party2
+                    } else {
+                        &party1
+                    };
+                    description = party.clone().unwrap_or_default();
+                }
+                EmptyDescription::Text(text) => description = text.clone(),
+            }
+        }
+        let Some(date) = date else {
+            bail!("Date missing at {}", line(result.position()))
+        };
+        let Some(party1) = &party1 else {
+            bail!("Party 1 missing at {}", line(result.position()))
+        };
+        let Some(party2) = &party2 else {
+            bail!("Party 2 missing at {}", line(result.position()))
+        };
+        let Some(amount) = amount else {
+            bail!("Amount missing at {}", line(result.position()))
+        };
+        let date = date.checked_add_signed(date_offset).ok_or_else(|| {
+            anyhow!(
+                "Shifting {} by the date_offset at {} is out of range",
+                date,
+                line(result.position())
+            )
+        })?;
+        let record = Record {
+            date,
+            party1,
+            party2,
+            amount,
+            description: &description,
+            memo: &memo,
+            currency: &currency,
+            txn_id: txn_id.as_deref().filter(|id| !id.is_empty()),
+            row: config
+                .row_numbers
+                .then(|| result.position().map(|position| position.line()))
+                .flatten(),
+        };
+        taker(record);
+        // debug!("{record:?}");
+    }
+    if empty_amounts > 0 {
+        warn!(
+            "{} rows with an empty amount were {}",
+            empty_amounts,
+            if config.empty_amount == EmptyAmount::Skip {
+                "skipped"
+            } else {
+                "treated as zero"
+            }
+        );
+    }
+    if short_rows > 0 {
+        warn!(
+            "{} rows with too few columns were {}",
+            short_rows,
+            if config.short_rows == ShortRows::Skip {
+                "skipped"
+            } else {
+                "padded"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Version of the JSON shape of [`Aggregate`], bump whenever it changes
+pub const SCHEMA_VERSION: u32 = 13;
+
+/// Minimal parser for OFX/QFX files, both the SGML and the XML variant. Only the statement
+/// transactions are read, everything else is ignored.
+pub fn import_ofx(content: &[u8], source: &str, mut taker: impl FnMut(Record)) -> Result<()> {
+    let encoding = if content.windows(12).any(|w| w == b"CHARSET:1252") {
+        encoding_rs::WINDOWS_1252
+    } else {
+        encoding_rs::UTF_8
+    };
+    let content = encoding.decode_without_bom_handling(content).0;
+    let mut fields: Option<AHashMap<String, String>> = None;
+    let mut currency = String::new();
+    for element in content.split('<').skip(1) {
+        let (tag, value) = element
+            .split_once('>')
+            .ok_or_else(|| anyhow!("Unterminated tag '<{}' in '{}'", element, source))?;
+        match tag.trim().to_ascii_uppercase().as_str() {
+            "STMTTRN" => fields = Some(AHashMap::new()),
+            "/STMTTRN" => {
+                let Some(fields) = fields.take() else {
+                    bail!("Unexpected </STMTTRN> in '{}'", source)
+                };
+                let get = |name: &str| fields.get(name).map(String::as_str).unwrap_or("");
+                let date = get("DTPOSTED");
+                // Dates may carry a time and timezone, ie. 20240131120000.000[-5:EST]
+                let date = NaiveDate::parse_from_str(date.get(..8).unwrap_or(date), "%Y%m%d")
+                    .with_context(|| format!("Parsing date '{}' in '{}'", date, source))?;
+                let amount = get("TRNAMT");
+                let amount: f64 = amount
+                    .replace(',', ".")
+                    .parse()
+                    .with_context(|| format!("Parsing amount '{}' in '{}'", amount, source))?;
+                let party = match get("NAME") {
+                    "" => get("PAYEE"),
+                    name => name,
+                };
+                taker(Record {
+                    date,
+                    party1: party,
+                    party2: party,
+                    description: get("MEMO"),
+                    memo: "",
+                    amount,
+                    currency: match get("CURSYM") {
+                        "" => currency.as_str(),
+                        currency => currency,
+                    },
+                    txn_id: Some(get("FITID")).filter(|id| !id.is_empty()),
+                    row: None,
+                });
+            }
+            "CURDEF" => currency = value.trim().to_string(),
+            tag => {
+                let value = value.trim();
+                if let Some(fields) = &mut fields {
+                    if !tag.starts_with('/') && !value.is_empty() {
+                        fields.insert(tag.to_string(), value.to_string());
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Identifies a record across files. Exact if the export has transaction ids, otherwise a
+/// fingerprint of date, parties and amount (in cents).
+#[derive(PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum RecordKey {
+    Id(String),
+    Fingerprint(NaiveDate, String, String, i64),
+}
+
+impl RecordKey {
+    /// The same key with the amount of a fingerprint changed by some cents
+    fn with_offset(&self, cents: i64) -> Self {
+        match self {
+            RecordKey::Id(id) => RecordKey::Id(id.clone()),
+            RecordKey::Fingerprint(date, party1, party2, amount) => {
+                RecordKey::Fingerprint(*date, party1.clone(), party2.clone(), amount + cents)
+            }
+        }
+    }
+}
+
+impl From<&Record<'_>> for RecordKey {
+    fn from(record: &Record<'_>) -> Self {
+        match record.txn_id {
+            Some(id) => RecordKey::Id(id.to_string()),
+            None => RecordKey::Fingerprint(
+                record.date,
+                record.party1.to_string(),
+                record.party2.to_string(),
+                (record.amount * 100.0).round() as i64,
+            ),
+        }
+    }
+}
+
+/// Detects records already seen in previous files. Identical records within one file are legitimate
+/// and only count as duplicates as far as a previous file contained them as often.
+#[derive(Default)]
+pub struct Dedup {
+    seen: AHashMap<RecordKey, usize>,
+    current: AHashMap<RecordKey, usize>,
+    /// Maximum difference in cents for fingerprints to still be equal
+    tolerance: i64,
+    pub duplicates: usize,
+}
+
+impl Dedup {
+    /// Treats amounts differing by up to the given cents as equal, ie. for amounts converted from
+    /// another currency and rounded differently. Fingerprints are kept in whole cents, so a sub-cent
+    /// difference such as 12.335 and 12.334 already needs a tolerance of 1. Transaction ids are
+    /// still matched exactly.
+    pub fn with_tolerance(mut self, cents: u32) -> Self {
+        self.tolerance = i64::from(cents);
+        self
+    }
+
+    pub fn is_duplicate(&mut self, record: &Record<'_>) -> bool {
+        let key = RecordKey::from(record);
+        // The exact amount first, then increasingly different ones
+        let offsets = match key {
+            RecordKey::Id(_) => 0..=0,
+            RecordKey::Fingerprint(..) => 0..=2 * self.tolerance,
+        }
+        .map(|i| if i % 2 == 0 { -i / 2 } else { i / 2 + 1 });
+        let close = offsets.map(|offset| key.with_offset(offset)).find(|key| {
+            let seen = self.seen.get(key).copied().unwrap_or(0);
+            self.current.get(key).copied().unwrap_or(0) < seen
+        });
+        let key = close.unwrap_or(key);
+        let seen = self.seen.get(&key).copied().unwrap_or(0);
+        let count = self.current.entry(key).or_insert(0);
+        *count += 1;
+        let duplicate = *count <= seen;
+        if duplicate {
+            self.duplicates += 1;
+        }
+        duplicate
+    }
+
+    pub fn finish_file(&mut self) {
+        for (key, count) in self.current.drain() {
+            let seen = self.seen.entry(key).or_insert(0);
+            *seen = (*seen).max(count);
+        }
+    }
+
+    /// Continues with the records seen by a previous run saved to the file, if it exists
+    pub fn load(file: &Path) -> Result<Self> {
+        if !file.exists() {
+            return Ok(Self::default());
+        }
+        let seen: Vec<(RecordKey, usize)> = serde_json::from_slice(&std::fs::read(file)?)
+            .with_context(|| format!("Reading seen records from '{}'", file.display()))?;
+        Ok(Self {
+            seen: seen.into_iter().collect(),
+            ..Self::default()
+        })
+    }
+
+    /// Saves the records seen so far, including the current file
+    pub fn save(&mut self, file: &Path) -> Result<()> {
+        self.finish_file();
+        let seen: Vec<_> = self.seen.iter().collect();
+        std::fs::write(file, serde_json::to_vec(&seen)?)
+            .with_context(|| format!("Writing seen records to '{}'", file.display()))?;
+        Ok(())
+    }
+}
+
+/// A [`Record`] owning its values, to keep it beyond the import
+#[derive(Debug, Clone, Default)]
+pub struct OwnedRecord {
+    pub date: NaiveDate,
+    pub party1: String,
+    pub party2: String,
+    pub description: String,
+    pub memo: String,
+    pub amount: f64,
+    pub currency: String,
+    pub txn_id: Option<String>,
+    pub row: Option<u64>,
+}
+
+impl From<&Record<'_>> for OwnedRecord {
+    fn from(record: &Record<'_>) -> Self {
+        Self {
+            date: record.date,
+            party1: record.party1.to_string(),
+            party2: record.party2.to_string(),
+            description: record.description.to_string(),
+            memo: record.memo.to_string(),
+            amount: record.amount,
+            currency: record.currency.to_string(),
+            txn_id: record.txn_id.map(str::to_string),
+            row: record.row,
+        }
+    }
+}
+
+impl OwnedRecord {
+    pub fn as_record(&self) -> Record<'_> {
+        Record {
+            date: self.date,
+            party1: &self.party1,
+            party2: &self.party2,
+            description: &self.description,
+            memo: &self.memo,
+            amount: self.amount,
+            currency: &self.currency,
+            txn_id: self.txn_id.as_deref(),
+            row: self.row,
+        }
+    }
+}
+
+/// Finds transfers between accounts, given the records of each account: an expense in one account
+/// and an income of the same amount and currency on the same day in another. Each record is part
+/// of at most one pair, earlier accounts and records are paired first. Returns whether each record
+/// is part of a pair, in the same shape as the accounts.
+pub fn match_transfers(accounts: &[Vec<OwnedRecord>]) -> Vec<Vec<bool>> {
+    let mut matched: Vec<Vec<bool>> = accounts
+        .iter()
+        .map(|records| vec![false; records.len()])
+        .collect();
+    let key = |record: &OwnedRecord| {
+        (
+            record.date,
+            (record.amount.abs() * 100.0).round() as i64,
+            record.currency.clone(),
+        )
+    };
+    let mut incomes: AHashMap<_, Vec<(usize, usize)>> = AHashMap::new();
+    for (account, records) in accounts.iter().enumerate() {
+        for (index, record) in records.iter().enumerate() {
+            if record.amount > 0.0 {
+                incomes
+                    .entry(key(record))
+                    .or_default()
+                    .push((account, index));
+            }
+        }
+    }
+    for (account, records) in accounts.iter().enumerate() {
+        for (index, record) in records.iter().enumerate() {
+            if record.amount >= 0.0 {
+                continue;
+            }
+            let Some(candidates) = incomes.get_mut(&key(record)) else {
+                continue;
+            };
+            if let Some(position) = candidates.iter().position(|(other, _)| *other != account) {
+                let (other, other_index) = candidates.remove(position);
+                matched[account][index] = true;
+                matched[other][other_index] = true;
+            }
+        }
+    }
+    matched
+}
+
+/// The result of a run, as written with `--json`. Fields added after the first schema version
+/// default to empty, so older reports can still be read.
+#[derive(Serialize, Deserialize)]
+pub struct Aggregate {
+    pub schema_version: u32,
+    pub tool_version: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub stats_summary: Vec<(String, f64)>,
+    pub stats_monthly: Vec<(MonthYear, Vec<(String, f64)>)>,
+    pub stats_grouped: Vec<(String, Vec<(MonthYear, f64)>)>,
+    #[serde(default)]
+    pub budgets: Vec<(String, Vec<BudgetMonth>)>,
+    /// Notes of groups, keyed by group name
+    #[serde(default)]
+    pub notes: BTreeMap<String, String>,
+    /// Most frequent words in descriptions by amount, if enabled
+    #[serde(default)]
+    pub keywords: Vec<Keyword>,
+    /// Records by size, if enabled
+    #[serde(default)]
+    pub histogram: Vec<Band>,
+    /// Largest records of each month, if enabled
+    #[serde(default)]
+    pub top_transactions: Vec<(MonthYear, Vec<Transaction>)>,
+    /// Records larger than the `highlight_over` amount, in the order imported
+    #[serde(default)]
+    pub large_transactions: Vec<Transaction>,
+    /// Parties with the most spending, if enabled
+    #[serde(default)]
+    pub top_parties: Vec<PartyTotal>,
+    /// Configured kinds of groups, keyed by group name
+    #[serde(default)]
+    pub kinds: BTreeMap<String, Kind>,
+    /// Groups left out of the totals and the net
+    #[serde(default)]
+    pub excluded_from_net: BTreeSet<String>,
+    /// How many records were assigned a group by a rule
+    #[serde(default)]
+    pub coverage: Coverage,
+    /// Total of the groups no rule matched, per month
+    #[serde(default)]
+    pub uncategorized: Vec<(MonthYear, f64)>,
+    /// Days from start to end left out with `exclude_range`, not counted for the figures per month
+    #[serde(default)]
+    pub excluded_days: i64,
+    /// Standard deviation of the monthly amounts of each group, in the order of the summary
+    #[serde(default)]
+    pub volatility: Vec<(String, f64)>,
+    /// How the report was created, missing if not written by `report`
+    pub metadata: Option<Metadata>,
+}
+
+/// Describes a run, so archived reports remain comprehensible
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Metadata {
+    /// Input files as given on the command line
+    pub files: Vec<String>,
+    /// Input CSV specification or combined config used, if any
+    pub format: Option<String>,
+    /// Local time the report was generated
+    pub generated: String,
+}
+
+/// A single record, as listed among the largest of a month
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+pub struct Transaction {
+    pub date: NaiveDate,
+    pub party: String,
+    pub description: String,
+    #[serde(default)]
+    pub memo: String,
+    pub group: String,
+    /// Line of the record in the imported file, if enabled by `row_numbers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub row: Option<u64>,
+    pub amount: f64,
+}
+
+/// Orders transactions by their absolute amount, to keep the largest in a heap
+#[derive(Clone)]
+struct BySize(Transaction);
+
+impl PartialEq for BySize {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BySize {}
+
+impl PartialOrd for BySize {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BySize {
+    fn cmp(&self, other: &Self) -> Ordering {
+        ordered_float::OrderedFloat(self.0.amount.abs())
+            .cmp(&ordered_float::OrderedFloat(other.0.amount.abs()))
+    }
+}
+
+/// Records matched by a rule (or assigned a group directly) out of all records, by number and by
+/// absolute amount
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+pub struct Coverage {
+    pub matched_count: usize,
+    pub total_count: usize,
+    pub matched_volume: f64,
+    pub total_volume: f64,
+}
+
+impl Coverage {
+    /// Share of the records matched, None without records
+    pub fn by_count(&self) -> Option<f64> {
+        (self.total_count > 0).then(|| self.matched_count as f64 / self.total_count as f64)
+    }
+
+    /// Share of the absolute amount matched, None without any amount
+    pub fn by_volume(&self) -> Option<f64> {
+        (self.total_volume > 0.0).then(|| self.matched_volume / self.total_volume)
+    }
+}
+
+/// Number and sum of the records within a range of absolute amounts
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Band {
+    /// Inclusive lower bound
+    pub from: f64,
+    /// Exclusive upper bound, none for the last band
+    pub to: Option<f64>,
+    pub income_count: usize,
+    pub income: f64,
+    pub expense_count: usize,
+    pub expenses: f64,
+}
+
+impl Display for Band {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        match self.to {
+            Some(to) => write!(f, "{}-{}", self.from, to),
+            None => write!(f, "{}+", self.from),
+        }
+    }
+}
+
+/// Spending with a party, as given in the records
+#[derive(Serialize, Deserialize)]
+pub struct PartyTotal {
+    pub party: String,
+    pub count: usize,
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Keyword {
+    pub word: String,
+    pub count: usize,
+    /// Sum of the absolute amounts of records containing the word
+    pub amount: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BudgetMonth {
+    pub month: MonthYear,
+    pub budget: f64,
+    /// Budget including what was rolled over from previous months
+    pub available: f64,
+    pub spent: f64,
+}
+
+/// Restrictions and group of a rule, its party pattern is in `Groups::party_set`
+#[derive(Clone)]
+struct GroupMatcher {
+    sign: Sign,
+    min: Option<f64>,
+    max: Option<f64>,
+    group: String,
+    /// The pattern again, to find the matched text if it names the group
+    use_match: Option<Regex>,
+}
+
+impl GroupMatcher {
+    fn matches(&self, amount: f64, expense: bool) -> bool {
+        self.sign.matches(expense)
+            && self.min.is_none_or(|min| amount.abs() >= min)
+            && self.max.is_none_or(|max| amount.abs() < max)
+    }
+
+    /// Name of the group for the text the pattern matched
+    fn group(&self, text: &str) -> String {
+        self.use_match
+            .as_ref()
+            .and_then(|regex| regex.find(text))
+            .map(|found| found.as_str())
+            .filter(|found| !found.is_empty())
+            .unwrap_or(&self.group)
+            .to_string()
+    }
+}
+
+/// Reads a report written with `--json`, warning if it has another schema version
+pub fn read_report(file: &Path) -> Result<Aggregate> {
+    let report: serde_json::Value = serde_json::from_slice(&std::fs::read(file)?)
+        .with_context(|| format!("Reading '{}'", file.display()))?;
+    // Reports per currency are keyed by currency instead
+    if report.get("schema_version").is_none()
+        && report.as_object().is_some_and(|currencies| {
+            !currencies.is_empty()
+                && currencies
+                    .values()
+                    .all(|report| report.get("schema_version").is_some())
+        })
+    {
+        bail!(
+            "'{}' is a report per currency, which can't be compared as a whole",
+            file.display()
+        );
+    }
+    let report: Aggregate = serde_json::from_value(report).with_context(|| {
+        format!(
+            "Reading '{}', is it a report written with --json?",
+            file.display()
+        )
+    })?;
+    if report.schema_version != SCHEMA_VERSION {
+        warn!(
+            "'{}' was written with schema version {}, expected {}",
+            file.display(),
+            report.schema_version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(report)
+}
+
+impl Aggregate {
+    /// Days from start to end, both inclusive, without those left out with `exclude_range`
+    pub fn days(&self) -> i64 {
+        (self.end - self.start).num_days() + 1 - self.excluded_days
+    }
+
+    /// Factor from the whole period to 30 days, if it has at least the given days
+    pub fn month_factor(&self, min_days: i64) -> Option<f64> {
+        let days = self.days();
+        (days >= min_days).then(|| 30.0 / days as f64)
+    }
+
+    /// Counts the days from start to end within any of the ranges, as left out of the records
+    pub fn exclude(&mut self, ranges: &[DateRange]) {
+        self.excluded_days = self
+            .start
+            .iter_days()
+            .take_while(|date| *date <= self.end)
+            .filter(|date| ranges.iter().any(|range| range.contains(*date)))
+            .count() as i64;
+    }
+
+    /// Sum of income and sum of expenses over all groups. Groups count by the sign of their sum,
+    /// unless they have a configured kind.
+    pub fn totals(&self) -> (f64, f64) {
+        self.stats_summary
+            .iter()
+            .fold((0.0, 0.0), |totals, (group, amount)| {
+                self.add_to_totals(totals, group, *amount)
+            })
+    }
+
+    /// Sum of income and sum of expenses per month, counted like `totals`
+    pub fn monthly_totals(&self) -> BTreeMap<MonthYear, (f64, f64)> {
+        let mut monthly_totals = BTreeMap::new();
+        for (group, values) in &self.stats_grouped {
+            for (month, amount) in values {
+                let totals = monthly_totals.entry(*month).or_insert((0.0, 0.0));
+                *totals = self.add_to_totals(*totals, group, *amount);
+            }
+        }
+        monthly_totals
+    }
+
+    fn add_to_totals(
+        &self,
+        (income, expenses): (f64, f64),
+        group: &str,
+        amount: f64,
+    ) -> (f64, f64) {
+        if self.excluded_from_net.contains(group) {
+            return (income, expenses);
+        }
+        match self.kinds.get(group) {
+            Some(Kind::Income) => (income + amount, expenses),
+            Some(Kind::Expense) => (income, expenses + amount),
+            Some(Kind::Neutral) => (income, expenses),
+            None if amount < 0.0 => (income, expenses + amount),
+            None => (income + amount, expenses),
+        }
+    }
+
+    /// Rounds all amounts to the given number of decimal places, for presentation. Totals are
+    /// computed from the rounded groups afterwards, so they add up, but may differ slightly from
+    /// the totals of the unrounded amounts.
+    pub fn round(&mut self, places: i32) {
+        let factor = 10f64.powi(places);
+        // Small negative amounts would round to -0.0 otherwise
+        let round = |amount: &mut f64| *amount = (*amount * factor).round() / factor + 0.0;
+        for (_, amount) in &mut self.stats_summary {
+            round(amount);
+        }
+        for (_, groups) in &mut self.stats_monthly {
+            for (_, amount) in groups {
+                round(amount);
+            }
+        }
+        for (_, months) in &mut self.stats_grouped {
+            for (_, amount) in months {
+                round(amount);
+            }
+        }
+        for (_, months) in &mut self.budgets {
+            for month in months {
+                round(&mut month.budget);
+                round(&mut month.available);
+                round(&mut month.spent);
+            }
+        }
+        for keyword in &mut self.keywords {
+            round(&mut keyword.amount);
+        }
+        for band in &mut self.histogram {
+            round(&mut band.income);
+            round(&mut band.expenses);
+        }
+        for (_, transactions) in &mut self.top_transactions {
+            for transaction in transactions {
+                round(&mut transaction.amount);
+            }
+        }
+        for transaction in &mut self.large_transactions {
+            round(&mut transaction.amount);
+        }
+        for party in &mut self.top_parties {
+            round(&mut party.amount);
+        }
+        for (_, amount) in &mut self.uncategorized {
+            round(amount);
+        }
+        for (_, amount) in &mut self.volatility {
+            round(amount);
+        }
+    }
+
+    /// The amounts of each group within the range. Months without records are left out.
+    pub fn series(&self, range: &MonthRange) -> Series {
+        let within = |month: &MonthYear| (range.from..=range.to).contains(month);
+        Series {
+            label: range.to_string(),
+            months: self
+                .stats_monthly
+                .iter()
+                .map(|(month, _)| *month)
+                .filter(within)
+                .collect(),
+            groups: self
+                .stats_grouped
+                .iter()
+                .map(|(group, values)| {
+                    let values = values
+                        .iter()
+                        .filter(|(month, _)| within(month))
+                        .map(|(_, amount)| *amount)
+                        .collect();
+                    (group.clone(), values)
+                })
+                .collect(),
+        }
+    }
+
+    /// Net sum over all groups per month
+    pub fn monthly_net(&self) -> BTreeMap<MonthYear, f64> {
+        let mut monthly_net = BTreeMap::new();
+        for (group, values) in &self.stats_grouped {
+            if self.excluded_from_net.contains(group) {
+                continue;
+            }
+            for (month, amount) in values {
+                *monthly_net.entry(*month).or_insert(0.0) += amount;
+            }
+        }
+        monthly_net
+    }
+}
+
+/// Amounts of the groups in the months of a range, to overlay several ranges in the graph
+#[derive(Serialize)]
+pub struct Series {
+    pub label: String,
+    pub months: Vec<MonthYear>,
+    /// Amount of each month, per group
+    pub groups: Vec<(String, Vec<f64>)>,
+}
+
+/// Share of the income that wasn't spent, (income - expenses) / income. Expenses are negative as
+/// returned by `Aggregate::totals`. None without income.
+pub fn savings_rate((income, expenses): (f64, f64)) -> Option<f64> {
+    (income > 0.0).then(|| (income + expenses) / income)
+}
+
+/// Expenses as share of the income, the complement of the savings rate. None without income.
+pub fn expense_ratio((income, expenses): (f64, f64)) -> Option<f64> {
+    (income > 0.0).then(|| -expenses / income)
+}
+
+/// The party without spaces and in upper case, if it looks like an IBAN: a country code, two
+/// check digits and up to 30 letters or digits. The checksum is not validated.
+fn normalize_iban(party: &str) -> Option<String> {
+    let iban: String = party
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase();
+    let bytes = iban.as_bytes();
+    let looks_like_iban = (15..=34).contains(&bytes.len())
+        && bytes[..2].iter().all(u8::is_ascii_uppercase)
+        && bytes[2..4].iter().all(u8::is_ascii_digit)
+        && bytes[4..].iter().all(u8::is_ascii_alphanumeric);
+    looks_like_iban.then_some(iban)
+}
+
+/// How the records of a group are combined
+#[derive(Debug, Deserialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Agg {
+    #[default]
+    Sum,
+    /// Average amount of the records
+    Avg,
+    /// Amount of the latest record
+    Last,
+    /// Largest absolute amount, keeping its sign, ie. the largest expense of an expense group
+    Max,
+}
+
+/// Collects what is needed for any [`Agg`] of a group
+#[derive(Clone, Copy)]
+struct Accumulator {
+    sum: f64,
+    count: usize,
+    last: Option<(NaiveDate, f64)>,
+    max: f64,
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self {
+            sum: 0.0,
+            count: 0,
+            last: None,
+            max: 0.0,
+        }
+    }
+}
+
+impl Accumulator {
+    fn add(&mut self, date: NaiveDate, amount: f64) {
+        self.sum += amount;
+        self.count += 1;
+        // Of records on the same day, the one imported last wins
+        if self.last.is_none_or(|(last, _)| date >= last) {
+            self.last = Some((date, amount));
+        }
+        if amount.abs() > self.max.abs() {
+            self.max = amount;
+        }
+    }
+
+    fn value(&self, agg: Agg) -> f64 {
+        match agg {
+            Agg::Sum => self.sum,
+            Agg::Avg => self.sum / self.count as f64,
+            Agg::Last => self.last.map_or(0.0, |(_, amount)| amount),
+            Agg::Max => self.max,
+        }
+    }
+}
+
+/// Line per month the groups no rule matched are summed up into, with `monthly_other`
+const OTHER: &str = "Other";
+
+#[derive(Clone)]
+pub struct Groups {
+    /// Party patterns of all rules, matched in one pass
+    party_set: RegexSet,
+    /// Rules in the order of `party_set`
+    group_matchers: Vec<GroupMatcher>,
+    unmatched: Unmatched,
+    monthly_top: usize,
+    monthly_other: bool,
+    normalize_iban: bool,
+    zero_amounts: ZeroAmounts,
+    split_by_direction: bool,
+    sources: Vec<Source>,
+    settings: BTreeMap<String, GroupSettings>,
+    /// Groups created for records no rule matched
+    fallback_groups: AHashSet<String>,
+    /// Number of records and absolute amount per word in descriptions, if enabled
+    keywords: Option<AHashMap<String, (usize, f64)>>,
+    /// Records by size, if enabled
+    histogram: Option<Vec<Band>>,
+    /// Number of records to keep per month in `largest`
+    top_transactions: usize,
+    /// Largest records per month, the smallest of them on top
+    largest: AHashMap<MonthYear, BinaryHeap<Reverse<BySize>>>,
+    highlight_over: Option<f64>,
+    /// Records larger than `highlight_over`
+    large: Vec<Transaction>,
+    /// Number of parties to list in `top_parties`
+    top_parties: usize,
+    /// Number and sum of expenses per party, if enabled
+    parties: AHashMap<String, (usize, f64)>,
+    volatility_active_months: bool,
+    /// Number of groups without a rule to warn about by name
+    unmatched_warnings: usize,
+    /// Number of groups without a rule so far
+    unmatched_count: usize,
+    coverage: Coverage,
+    stats_summary: AHashMap<String, Accumulator>,
+    stats_monthly: AHashMap<MonthYear, AHashMap<String, Accumulator>>,
+    start: NaiveDate,
+    end: NaiveDate,
+    /// First and last day of the imported period, if restricted, instead of those of the records
+    period: (Option<NaiveDate>, Option<NaiveDate>),
+}
+
+/// Population standard deviation, zero without amounts
+fn standard_deviation(amounts: &[f64]) -> f64 {
+    if amounts.is_empty() {
+        return 0.0;
+    }
+    let count = amounts.len() as f64;
+    let mean = amounts.iter().sum::<f64>() / count;
+    let variance = amounts
+        .iter()
+        .map(|amount| (amount - mean).powi(2))
+        .sum::<f64>()
+        / count;
+    variance.sqrt()
+}
+
+/// Describes each of the patterns that is not a valid regular expression, with the error
+pub fn invalid_patterns<'a>(patterns: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    patterns
+        .into_iter()
+        .filter_map(|pattern| {
+            Regex::new(pattern)
+                .err()
+                .map(|error| format!("'{pattern}': {error}"))
+        })
+        .collect()
+}
+
+impl Groups {
+    pub fn new(config: GroupConfig, analysis: &AnalysisOptions) -> Result<Self> {
+        // Invalid patterns are skipped, like before matching them all at once. Only parsed here,
+        // the set is what compiles them.
+        let (patterns, group_matchers): (Vec<_>, Vec<_>) = config
+            .parties
+            .iter()
+            .filter(|(regex, _)| regex_syntax::Parser::new().parse(regex).is_ok())
+            .map(|(regex, rule)| {
+                let (group, sign, min, max, use_match) = match rule {
+                    PartyRule::Group(group) => (group.clone(), Sign::Any, None, None, false),
+                    PartyRule::Detailed {
+                        group,
+                        sign,
+                        min,
+                        max,
+                        use_match,
+                    } => (group.clone(), *sign, *min, *max, *use_match),
+                };
+                let matcher = GroupMatcher {
+                    sign,
+                    min,
+                    max,
+                    group,
+                    use_match: use_match.then(|| Regex::new(regex).ok()).flatten(),
+                };
+                (regex.as_str(), matcher)
+            })
+            .unzip();
+        Ok(Self {
+            party_set: RegexSet::new(patterns)?,
+            stats_summary: AHashMap::new(),
+            stats_monthly: AHashMap::new(),
+            group_matchers,
+            unmatched: config.unmatched,
+            monthly_top: config.monthly_top.unwrap_or(20),
+            monthly_other: config.monthly_other,
+            normalize_iban: config.normalize_iban,
+            zero_amounts: config.zero_amounts,
+            split_by_direction: config.split_by_direction,
+            sources: if config.sources.is_empty() {
+                vec![Source::Party]
+            } else {
+                config.sources
+            },
+            settings: config.settings,
+            fallback_groups: AHashSet::new(),
+            keywords: analysis.keywords.then(AHashMap::new),
+            top_transactions: analysis.top_transactions,
+            largest: AHashMap::new(),
+            highlight_over: analysis.highlight_over,
+            large: vec![],
+            top_parties: analysis.top_parties,
+            volatility_active_months: analysis.volatility_active_months,
+            unmatched_warnings: analysis.unmatched_warnings.unwrap_or(20),
+            unmatched_count: 0,
+            parties: AHashMap::new(),
+            coverage: Coverage::default(),
+            histogram: analysis.histogram.then(|| {
+                let mut bounds: Vec<_> = analysis
+                    .bands
+                    .iter()
+                    .copied()
+                    .filter(|b| *b > 0.0)
+                    .collect();
+                bounds.sort_by_key(|bound| ordered_float::OrderedFloat(*bound));
+                bounds.dedup();
+                std::iter::once(0.0)
+                    .chain(bounds.iter().copied())
+                    .zip(bounds.iter().copied().map(Some).chain([None]))
+                    .map(|(from, to)| Band {
+                        from,
+                        to,
+                        income_count: 0,
+                        income: 0.0,
+                        expense_count: 0,
+                        expenses: 0.0,
+                    })
+                    .collect()
+            }),
+            start: NaiveDate::MAX,
+            end: NaiveDate::MIN,
+            period: (None, None),
+        })
+    }
+
+    /// Reports the given first and last day, both inclusive, instead of those of the records. The
+    /// figures per month are then relative to the whole period, even if its first or last days
+    /// have no records.
+    pub fn set_period(&mut self, first: Option<NaiveDate>, last: Option<NaiveDate>) {
+        self.period = (first, last);
+    }
+
+    /// Whether the record counts as expense, matched by its payee
+    fn is_expense(&self, record: &Record<'_>) -> bool {
+        record.amount < 0.0 || (record.amount == 0.0 && self.zero_amounts == ZeroAmounts::Expense)
+    }
+
+    pub fn push(&mut self, record: Record<'_>) {
+        let mut hit = true;
+        let expense = self.is_expense(&record);
+        let party = if expense {
+            record.party2
+        } else {
+            record.party1
+        };
+        if self.top_parties > 0 && expense {
+            let (count, amount) = self.parties.entry(party.to_string()).or_insert((0, 0.0));
+            *count += 1;
+            *amount += record.amount;
+        }
+        let key = self
+            .normalize_iban
+            .then(|| normalize_iban(party))
+            .flatten()
+            .unwrap_or_else(|| party.to_lowercase());
+        let matched = self.sources.iter().find_map(|source| {
+            let text = match source {
+                Source::Party => Cow::Borrowed(key.as_str()),
+                Source::Description => Cow::Owned(record.description.to_lowercase()),
+                Source::Memo => Cow::Owned(record.memo.to_lowercase()),
+            };
+            let mut candidates = self
+                .party_set
+                .matches(&text)
+                .into_iter()
+                .map(|index| &self.group_matchers[index])
+                .filter(|matcher| matcher.matches(record.amount, expense));
+            // The first rule in key order wins
+            let first = candidates.next()?;
+            let others: Vec<_> = candidates.map(|matcher| matcher.group.as_str()).collect();
+            if !others.is_empty() {
+                debug!(
+                    "'{}' matches several rules, using '{}' over {:?}",
+                    text, first.group, others
+                );
+            }
+            Some(first.group(&text))
+        });
+        let key = match matched {
+            Some(group) => group,
+            None => {
+                hit = false;
+                self.unmatched.group(&key)
+            }
+        };
+        let key = match (self.split_by_direction, expense) {
+            (false, _) => key,
+            (true, false) => format!("{key} (in)"),
+            (true, true) => format!("{key} (out)"),
+        };
+        self.add(record, key, hit);
+    }
+
+    /// Adds the record to the given group, regardless of the rules
+    pub fn push_to(&mut self, record: Record<'_>, group: &str) {
+        self.add(record, group.to_string(), true);
+    }
+
+    fn add(&mut self, record: Record<'_>, key: String, hit: bool) {
+        // Adding zero turns -0.0, ie. an inverted zero amount, into 0.0, which sorts and shows
+        // like any other zero
+        let record = Record {
+            amount: record.amount + 0.0,
+            ..record
+        };
+        let key = &key;
+        if !hit && !self.fallback_groups.contains(key) {
+            self.fallback_groups.insert(key.clone());
+        }
+        let amount = match self.settings.get(key) {
+            Some(settings) if settings.absolute => record.amount.abs(),
+            _ => record.amount,
+        };
+        self.stats_summary
+            .entry(key.clone())
+            .or_insert_with(|| {
+                if !hit {
+                    if self.unmatched_count < self.unmatched_warnings {
+                        warn!("No group mapping found for '{}'", key);
+                    }
+                    self.unmatched_count += 1;
+                }
+                Accumulator::default()
+            })
+            .add(record.date, amount);
+        self.stats_monthly
+            .entry(record.date.into())
+            .or_insert_with(AHashMap::new)
+            .entry(key.clone())
+            .or_default()
+            .add(record.date, amount);
+        self.start = self.start.min(record.date);
+        self.end = self.end.max(record.date);
+        self.coverage.total_count += 1;
+        self.coverage.total_volume += record.amount.abs();
+        if hit {
+            self.coverage.matched_count += 1;
+            self.coverage.matched_volume += record.amount.abs();
+        }
+        if let Some(keywords) = &mut self.keywords {
+            let description = record.description.to_lowercase();
+            // Short words and numbers are mostly noise like dates or reference numbers
+            let words: AHashSet<_> = description
+                .split(|c: char| !c.is_alphanumeric())
+                .filter(|word| word.chars().count() >= 3 && !word.chars().all(|c| c.is_numeric()))
+                .collect();
+            for word in words {
+                let (count, amount) = keywords.entry(word.to_string()).or_insert((0, 0.0));
+                *count += 1;
+                *amount += record.amount.abs();
+            }
+        }
+        let expense = self.is_expense(&record);
+        if let Some(histogram) = &mut self.histogram {
+            let size = record.amount.abs();
+            if let Some(band) = histogram.iter_mut().rev().find(|band| size >= band.from) {
+                if expense {
+                    band.expense_count += 1;
+                    band.expenses += record.amount;
+                } else {
+                    band.income_count += 1;
+                    band.income += record.amount;
+                }
+            }
+        }
+        let large = self
+            .highlight_over
+            .is_some_and(|over| record.amount.abs() > over);
+        if self.top_transactions > 0 || large {
+            let party = if self.is_expense(&record) {
+                record.party2
+            } else {
+                record.party1
+            };
+            let transaction = Transaction {
+                date: record.date,
+                party: party.to_string(),
+                description: record.description.to_string(),
+                memo: record.memo.to_string(),
+                group: key.clone(),
+                row: record.row,
+                amount: record.amount,
+            };
+            if large {
+                self.large.push(transaction.clone());
+            }
+            if self.top_transactions > 0 {
+                let largest = self.largest.entry(record.date.into()).or_default();
+                let larger = largest
+                    .peek()
+                    .is_none_or(|Reverse(smallest)| record.amount.abs() > smallest.0.amount.abs());
+                if largest.len() < self.top_transactions || larger {
+                    largest.push(Reverse(BySize(transaction)));
+                    if largest.len() > self.top_transactions {
+                        largest.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn aggregate(self) -> Result<Aggregate> {
+        if self.unmatched_count > self.unmatched_warnings {
+            warn!(
+                "No group mapping found for {} more groups",
+                self.unmatched_count - self.unmatched_warnings
+            );
+        }
+        let agg = |group: &str| self.settings.get(group).map_or(Agg::Sum, |s| s.agg);
+        let mut stats_summary: Vec<_> = self
+            .stats_summary
+            .iter()
+            .map(|(group, acc)| (group.clone(), acc.value(agg(group))))
+            .collect();
+        let monthly: AHashMap<MonthYear, AHashMap<&String, f64>> = self
+            .stats_monthly
+            .iter()
+            .map(|(m_y, e)| {
+                let e = e
+                    .iter()
+                    .map(|(group, acc)| (group, acc.value(agg(group))))
+                    .collect();
+                (*m_y, e)
+            })
+            .collect();
+        // Ties are broken by name, to keep the order of groups stable between runs
+        stats_summary.sort_by(|(a_group, a_amount), (b_group, b_amount)| {
+            ordered_float::OrderedFloat(*a_amount)
+                .cmp(&ordered_float::OrderedFloat(*b_amount))
+                .then_with(|| a_group.cmp(b_group))
+        });
+
+        let mut stats_monthly: Vec<_> = monthly
+            .iter()
+            .map(|(m_y, e)| {
+                let mut other = 0.0;
+                let mut entries: Vec<_> = e
+                    .iter()
+                    .filter(|(group, amount)| {
+                        // Including a configured "Other" group, rather than listing it twice
+                        let is_other = self.monthly_other
+                            && (self.fallback_groups.contains(**group) || **group == OTHER);
+                        if is_other {
+                            other += *amount;
+                        }
+                        !is_other
+                    })
+                    .map(|(group, amount)| (group.to_string(), *amount))
+                    .collect();
+                entries.sort_by_key(|(_, amount)| ordered_float::OrderedFloat(-amount.abs()));
+                entries.truncate(self.monthly_top);
+                if other != 0.0 {
+                    entries.push((OTHER.to_string(), other));
+                }
+
+                (*m_y, entries)
+            })
+            .collect();
+        stats_monthly.sort_by_key(|(m_y, _)| *m_y);
+        let uncategorized = stats_monthly
+            .iter()
+            .map(|(m_y, _)| {
+                let amount = monthly[m_y]
+                    .iter()
+                    .filter(|(group, _)| self.fallback_groups.contains(**group))
+                    .fold(0.0, |sum, (_, amount)| sum + amount);
+                (*m_y, amount)
+            })
+            .collect();
+        let stats_grouped: Vec<_> = stats_summary
+            .iter()
+            .map(|(g, _)| {
+                let values: Vec<_> = stats_monthly
+                    .iter()
+                    .map(|(m_y, _)| (*m_y, monthly[m_y].get(g).cloned().unwrap_or(0.0)))
+                    .collect();
+                (g.clone(), values)
+            })
+            .collect();
+        let volatility = stats_summary
+            .iter()
+            .map(|(group, _)| {
+                let amounts: Vec<_> = stats_monthly
+                    .iter()
+                    .filter_map(|(m_y, _)| match monthly[m_y].get(group) {
+                        Some(amount) => Some(*amount),
+                        None => (!self.volatility_active_months).then_some(0.0),
+                    })
+                    .collect();
+                (group.clone(), standard_deviation(&amounts))
+            })
+            .collect();
+        let budgets = stats_grouped
+            .iter()
+            .filter_map(|(group, values)| {
+                let settings = self.settings.get(group)?;
+                let budget = settings.budget?;
+                let mut carry = 0.0;
+                let months = values
+                    .iter()
+                    .map(|(month, amount)| {
+                        let available = budget + carry;
+                        let spent = -amount;
+                        carry = if settings.rollover {
+                            available - spent
+                        } else {
+                            0.0
+                        };
+                        BudgetMonth {
+                            month: *month,
+                            budget,
+                            available,
+                            spent,
+                        }
+                    })
+                    .collect();
+                Some((group.clone(), months))
+            })
+            .collect();
+
+        let mut keywords: Vec<_> = self
+            .keywords
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(word, (count, amount))| Keyword {
+                word,
+                count,
+                amount,
+            })
+            .collect();
+        keywords.sort_by(|a, b| {
+            ordered_float::OrderedFloat(b.amount)
+                .cmp(&ordered_float::OrderedFloat(a.amount))
+                .then_with(|| a.word.cmp(&b.word))
+        });
+        keywords.truncate(30);
+
+        let mut top_transactions: Vec<_> = self
+            .largest
+            .into_iter()
+            .map(|(month, largest)| {
+                // Largest first
+                let transactions = largest
+                    .into_sorted_vec()
+                    .into_iter()
+                    .map(|Reverse(BySize(transaction))| transaction)
+                    .collect();
+                (month, transactions)
+            })
+            .collect();
+        top_transactions.sort_by_key(|(month, _)| *month);
+
+        let mut top_parties: Vec<_> = self
+            .parties
+            .into_iter()
+            .map(|(party, (count, amount))| PartyTotal {
+                party,
+                count,
+                amount,
+            })
+            .collect();
+        // Most spending first, the amounts being negative
+        top_parties.sort_by(|a, b| {
+            ordered_float::OrderedFloat(a.amount)
+                .cmp(&ordered_float::OrderedFloat(b.amount))
+                .then_with(|| a.party.cmp(&b.party))
+        });
+        top_parties.truncate(self.top_parties);
+
+        Ok(Aggregate {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            start: self.period.0.unwrap_or(self.start),
+            end: self.period.1.unwrap_or(self.end),
+            stats_summary,
+            stats_monthly,
+            stats_grouped,
+            budgets,
+            notes: self
+                .settings
+                .iter()
+                .filter_map(|(group, settings)| Some((group.clone(), settings.notes.clone()?)))
+                .collect(),
+            keywords,
+            histogram: self.histogram.unwrap_or_default(),
+            top_transactions,
+            large_transactions: self.large,
+            top_parties,
+            kinds: self
+                .settings
+                .iter()
+                .filter_map(|(group, settings)| Some((group.clone(), settings.kind?)))
+                .collect(),
+            excluded_from_net: self
+                .settings
+                .iter()
+                .filter(|(_, settings)| settings.exclude_from_net)
+                .map(|(group, _)| group.clone())
+                .collect(),
+            coverage: self.coverage,
+            uncategorized,
+            excluded_days: 0,
+            volatility,
+            metadata: None,
+        })
+    }
+}
+
+/// Reads group rules from a CSV with the columns substring and group, the first row being a
+/// header. Substrings are matched literally instead of as regular expressions.
+pub fn read_group_csv(file: &Path) -> Result<GroupConfig> {
+    let content = std::fs::read(file)?;
+    let first_line = content.split(|b| *b == b'\n').next().unwrap_or_default();
+    let delimiter = if first_line.contains(&b';') && !first_line.contains(&b',') {
+        b';'
+    } else {
+        b','
+    };
+    let mut rdr = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .from_reader(&content[..]);
+    let mut parties = BTreeMap::new();
+    for record in rdr.records() {
+        let record = record?;
+        let (Some(substring), Some(group)) = (record.get(0), record.get(1)) else {
+            bail!(
+                "Expected substring and group in '{}' at {}",
+                file.display(),
+                line(record.position())
+            )
+        };
+        // Parties are matched in lower case, or upper case for IBANs
+        parties.insert(
+            format!("(?i){}", regex::escape(substring.trim())),
+            PartyRule::Group(group.trim().to_string()),
+        );
+    }
+    Ok(GroupConfig {
+        parties,
+        ..Default::default()
+    })
+}
+
+/// Writes a starter input specification for a sample CSV. The header is taken to be the row with
+/// the most columns within the first lines, skipping any preamble. Fields are guessed from common
+/// column names, other columns are listed commented out.
+pub fn scaffold_format(sample: &[u8]) -> Result<String> {
+    // Number of columns of the first lines for a delimiter
+    let rows = |delimiter: u8| -> Vec<usize> {
+        ReaderBuilder::new()
+            .delimiter(delimiter)
+            .flexible(true)
+            .has_headers(false)
+            .from_reader(sample)
+            .into_byte_records()
+            .take(20)
+            .map_while(|record| record.ok())
+            .map(|record| record.len())
+            .collect()
+    };
+    let (delimiter, columns) = [b';', b',', b'\t']
+        .into_iter()
+        .map(|delimiter| (delimiter, rows(delimiter)))
+        .max_by_key(|(_, columns)| columns.iter().max().copied().unwrap_or(0))
+        .expect("Delimiters given");
+    let Some(widest) = columns.iter().max() else {
+        bail!("No rows found in the sample");
+    };
+    let skip = columns.iter().position(|len| len == widest).unwrap_or(0);
+    if delimiter != b';' {
+        warn!(
+            "The sample looks separated by {:?}, but only ';' is supported for importing",
+            delimiter as char
+        );
+    }
+    let header = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(sample)
+        .into_byte_records()
+        .nth(skip)
+        .ok_or_else(|| anyhow!("No header row found"))??;
+    let guess = |header: &str| {
+        let header = header.to_lowercase();
+        let any = |names: &[&str]| names.iter().any(|name| header.contains(name));
+        if any(&["date", "datum", "buchungstag"]) {
+            Some("date")
+        } else if any(&["amount", "betrag", "value"]) {
+            Some("amount")
+        } else if any(&["description", "verwendungszweck", "memo", "purpose"]) {
+            Some("description")
+        } else if any(&["currency", "währung"]) {
+            Some("currency")
+        } else if any(&["payee", "empfänger", "auftraggeber", "counterparty", "name"]) {
+            Some("party")
+        } else {
+            None
+        }
+    };
+    let mut format = String::new();
+    if skip > 0 {
+        format += &format!("skip = {skip}\n");
+    }
+    format += "# Adjust to the dates of the file, see https://docs.rs/chrono/latest/chrono/format/strftime\n";
+    format += "date_format = \"%d.%m.%Y\"\n";
+    format += "# number_locale = \"de\"\n";
+    format += "\n[map]\n";
+    format +=
+        "# Fields: date, party (or party1 and party2), amount, description, memo, currency, txn_id\n";
+    let mut guessed = AHashSet::new();
+    for header in header.iter() {
+        let header = String::from_utf8_lossy(header);
+        let header = header.trim();
+        if header.is_empty() {
+            continue;
+        }
+        // Every field only once, the first matching column is usually the right one
+        let field = guess(header).filter(|field| guessed.insert(*field));
+        let key = toml::Value::String(format!("^{}$", regex::escape(header)));
+        match field {
+            Some(field) => format += &format!("{key} = \"{field}\"\n"),
+            None => format += &format!("# {key} = \"\"\n"),
+        }
+    }
+    Ok(format)
+}
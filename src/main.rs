@@ -1,346 +1,1663 @@
-use ahash::*;
 use anyhow::{anyhow, bail, Context, Result};
-use chrono::{Datelike, NaiveDate};
+use chrono::format::{Item, StrftimeItems};
 use clap::Parser;
-use csv::ReaderBuilder;
-use num_format::{parsing::ParseFormatted, Locale};
-use regex::Regex;
-use rust_xlsxwriter::{Format, Workbook, XlsxColor};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::cmp::Ordering;
-use std::collections::BTreeMap;
-use std::fmt::{Display, Error, Formatter};
-use std::path::PathBuf;
+use rust_xlsxwriter::{
+    ConditionalFormat3ColorScale, ConditionalFormatType, DocProperties, Format, Workbook,
+    Worksheet, XlsxColor,
+};
+use spenben::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use tokio::runtime::Runtime;
+use tracing::{info, warn};
+use tracing_subscriber::EnvFilter;
 use warp::Filter;
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize)]
-struct MonthYear {
-    month: u32,
-    year: i32,
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Without a subcommand, a report is written like with `report`
+    #[command(flatten)]
+    report: ReportArgs,
+    /// Emit log messages as JSON. The log level is controlled by `RUST_LOG`, ie. `RUST_LOG=debug`
+    #[arg(long, global = true)]
+    log_json: bool,
+    /// Only log errors and don't print the summary, ie. for cron jobs. Reports requested as JSON
+    /// or text on stdout are still printed.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+}
+
+/// Prints like `println!`, unless the first argument, `--quiet`, is set
+macro_rules! summary {
+    ($quiet:expr) => {
+        if !$quiet {
+            println!();
+        }
+    };
+    ($quiet:expr, $($arg:tt)*) => {
+        if !$quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// Import statements and write a report (the default)
+    Report(Box<ReportArgs>),
+    /// Import statements and host a web server showing a graph
+    Serve(ServeArgs),
+    /// Import statements and only report problems, without writing a report
+    Validate(InputArgs),
+    /// Compare two reports written with `--json`, not `--per-currency`
+    Diff { old: PathBuf, new: PathBuf },
+    /// Write a starter input CSV specification for a sample file, to be completed by hand
+    Init {
+        sample: PathBuf,
+        /// File to write the specification to instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Which statements to import and how
+#[derive(clap::Args)]
+struct InputArgs {
+    /// CSV or OFX/QFX Files or http(s) URLs to import, glob patterns are expanded. "-" reads a
+    /// single CSV file from stdin.
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    /// Group mapping file, either TOML or a CSV with the columns substring and group. Can be
+    /// repeated to merge rules and settings, other options are taken from the first file.
+    #[arg(short, long)]
+    groups: Vec<PathBuf>,
+    /// What to do if group files map the same pattern to different rules
+    #[arg(long, value_enum, default_value_t = GroupConflicts::Error)]
+    group_conflicts: GroupConflicts,
+    /// Input CSV specification, not required for OFX/QFX files
+    #[arg(short = 'i', long, alias = "ff")]
+    file_format: Option<PathBuf>,
+    /// Combined file with the input CSV specification in `[import]` and the group mapping in `[groups]`
+    #[arg(short, long, conflicts_with_all = ["file_format", "groups"])]
+    config: Option<PathBuf>,
+    /// HTTP header for files given as URL, ie. "Authorization: Bearer <token>". Prefer `keyring`
+    /// in the format for tokens, which is used unless an "Authorization" header is given.
+    #[arg(long = "header", value_name = "NAME: VALUE")]
+    headers: Vec<String>,
+    /// Skip records already imported from a previous file, ie. for overlapping exports
+    #[arg(long)]
+    dedup: bool,
+    /// Treat amounts differing by up to this many cents as equal when skipping records already
+    /// imported, ie. for amounts converted from another currency
+    #[arg(long, value_name = "CENTS", default_value_t = 0)]
+    dedup_tolerance: u32,
+    /// Leave out records within this range of days, ie. "2024-07-01:2024-07-21" for a vacation
+    /// distorting the usual spending. Can be given multiple times. The figures per month are
+    /// calculated without these days.
+    #[arg(long, value_name = "FROM:TO")]
+    exclude_range: Vec<DateRange>,
+    /// Only import records from this day on, ie. "2024-01-01"
+    #[arg(long, value_name = "DATE")]
+    from: Option<chrono::NaiveDate>,
+    /// Only import records up to and including this day, see `--exclusive-end`
+    #[arg(long, value_name = "DATE")]
+    to: Option<chrono::NaiveDate>,
+    /// Leave out the records on the day given by `--to`, ie. to report adjacent periods like
+    /// `--from 2024-01-01 --to 2024-02-01` and `--from 2024-02-01 --to 2024-03-01` without counting
+    /// a day twice
+    #[arg(long, requires = "to")]
+    exclusive_end: bool,
+    /// Fail if a configured field matches no column, instead of only warning
+    #[arg(long)]
+    strict_headers: bool,
+    /// Fail if a party or column pattern is not a valid regular expression, instead of only
+    /// warning and skipping it. Implies `--strict-headers`.
+    #[arg(long)]
+    strict: bool,
+    /// Detect transfers between own accounts, one per file: an expense and an income of the same
+    /// amount on the same day in different files
+    #[arg(long, value_enum)]
+    internal_transfers: Option<InternalTransfers>,
+}
+
+#[derive(clap::Args)]
+struct ReportArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Print the aggregated data as JSON to stdout instead of writing a report, same as `--format json`
+    #[arg(long, conflicts_with = "format")]
+    json: bool,
+    /// Indent the JSON for reading it, instead of a single line for further processing
+    #[arg(long)]
+    pretty: bool,
+    /// Additionally write the monthly sums per group as long format CSV (month, group, amount)
+    #[arg(long)]
+    tidy_csv: Option<PathBuf>,
+    /// Leave out months without any amount in the long format CSV
+    #[arg(long, requires = "tidy_csv")]
+    tidy_skip_zero: bool,
+    /// Report each currency separately instead of summing everything up, requires a mapped
+    /// currency column. The JSON report is then an object keyed by currency, which `diff` can't
+    /// compare.
+    #[arg(long, conflicts_with = "tidy_csv")]
+    per_currency: bool,
+    /// Run a command after the report was written, receiving the report as JSON on its stdin.
+    /// The run fails if the command exits with a non-zero code.
+    #[arg(long, value_name = "COMMAND")]
+    exec: Option<String>,
+    /// Only report records not seen by a previous run with the same file, which remembers all
+    /// imported records. On the first run, when the file doesn't exist yet, everything is new.
+    /// Records left out with `--from`, `--to` or `--exclude-range` are still remembered.
+    #[arg(long, value_name = "FILE")]
+    since_last: Option<PathBuf>,
+    /// Additionally write every imported record as one JSON object per line, as they are read
+    #[arg(long, value_name = "PATH")]
+    export_jsonl: Option<PathBuf>,
+    /// Kinds of report to produce, separated by commas, ie. "xlsx,json"
+    #[arg(short, long, value_enum, value_delimiter = ',', default_values_t = [OutputFormat::Xlsx])]
+    format: Vec<OutputFormat>,
+    /// Base name of the report files, extended by the format (ie. "report.xlsx"). JSON is written
+    /// to stdout without it, so it is required for JSON along with other formats. Text is always
+    /// printed.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    #[command(flatten)]
+    analysis: AnalysisArgs,
+    #[command(flatten)]
+    xlsx: XlsxArgs,
 }
 
-impl Display for MonthYear {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        write!(f, "{} {}", self.year, self.month)
+/// Additional analyses of the records
+#[derive(clap::Args)]
+struct AnalysisArgs {
+    /// List the most frequent words in descriptions, weighted by amount
+    #[arg(long)]
+    keywords: bool,
+    /// Count and sum up records by size, separately for income and expenses
+    #[arg(long)]
+    histogram: bool,
+    /// Boundaries between the size bands of the histogram
+    #[arg(long, value_delimiter = ',', default_values_t = [10.0, 50.0, 200.0])]
+    bands: Vec<f64>,
+    /// List the given number of largest records of each month
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    top_transactions: usize,
+    /// Highlight records larger than this absolute amount in the Top Transactions sheet, and
+    /// their groups in the Summary
+    #[arg(long, value_name = "AMOUNT")]
+    highlight_over: Option<f64>,
+    /// List the given number of parties with the most spending, regardless of the groups, ie. to
+    /// find parties without a rule
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    top_parties: usize,
+    /// Leave the months without records of a group out of its volatility, instead of counting
+    /// them as zero. Zero months make irregular groups appear more volatile.
+    #[arg(long)]
+    volatility_active_months: bool,
+    /// Warn about at most this many groups without a rule by name, 20 if not given. The others
+    /// are only counted.
+    #[arg(long, value_name = "N")]
+    unmatched_warnings: Option<usize>,
+}
+
+impl From<&AnalysisArgs> for AnalysisOptions {
+    fn from(args: &AnalysisArgs) -> Self {
+        Self {
+            keywords: args.keywords,
+            histogram: args.histogram,
+            bands: args.bands.clone(),
+            top_transactions: args.top_transactions,
+            highlight_over: args.highlight_over,
+            top_parties: args.top_parties,
+            volatility_active_months: args.volatility_active_months,
+            unmatched_warnings: args.unmatched_warnings,
+        }
     }
 }
 
-impl PartialOrd for MonthYear {
-    fn partial_cmp(&self, rhs: &Self) -> Option<Ordering> {
-        Some(self.cmp(&rhs))
+/// Options for the presentation of the xlsx report
+#[derive(clap::Args)]
+struct XlsxArgs {
+    /// Minimum number of days of data to show figures normalized per month, which would be
+    /// misleading for shorter periods
+    #[arg(long, default_value_t = 14)]
+    min_days: i64,
+    /// Round the figures normalized per month to whole currency units
+    #[arg(long)]
+    round_monthly: bool,
+    /// Leave out the figures normalized per month, listing only the totals of the groups
+    #[arg(long, conflicts_with_all = ["round_monthly", "min_days"])]
+    no_normalize: bool,
+    /// Title of the report, shown above the period in the Summary sheet and stored as document
+    /// title, ie. "Q1 2024 - Joint Account"
+    #[arg(long)]
+    title: Option<String>,
+    /// Round all amounts shown in the report to this many decimal places, to whole currency units
+    /// if no number is given. Totals are summed from the rounded groups. The JSON report keeps the
+    /// full precision.
+    #[arg(long, value_name = "PLACES", num_args = 0..=1, default_missing_value = "0")]
+    round_display: Option<i32>,
+    /// Don't freeze the header rows and add filters to the summary sheets
+    #[arg(long)]
+    no_freeze: bool,
+    /// Print a single line per month with the net and the two largest expenses, instead of
+    /// every group
+    #[arg(long)]
+    compact: bool,
+    /// List every group in every month of the monthly summary, with zero if inactive, instead
+    /// of only the top groups of each month
+    #[arg(long)]
+    all_groups: bool,
+    /// Add a sheet with the amount of each group per month, colored by size
+    #[arg(long)]
+    heatmap: bool,
+    /// Leave the euro symbol out of the number format of amounts, ie. for further calculations
+    /// in the spreadsheet. Negative amounts are still shown in red.
+    #[arg(long)]
+    no_currency_symbol: bool,
+    /// Width of the columns with the given header in all sheets, instead of fitting the content,
+    /// ie. "Description=40". Can be given multiple times or separated by commas.
+    #[arg(long, value_name = "HEADER=WIDTH", value_delimiter = ',', value_parser = parse_column_width)]
+    column_width: Vec<(String, f64)>,
+    /// Set from the global `--quiet`, to not print the summary
+    #[arg(skip)]
+    quiet: bool,
+    /// Label months in the sheets with this chrono format instead of "2024 3", ie. "%B %Y" for
+    /// "March 2024" or "%Y-%m" for further processing
+    #[arg(long, value_name = "FORMAT", value_parser = parse_month_format)]
+    month_format: Option<String>,
+    /// Locale of the month names in `--month-format`, ie. "de_DE"
+    #[arg(long, default_value = "en_US", value_parser = parse_locale)]
+    month_locale: chrono::Locale,
+}
+
+fn parse_month_format(value: &str) -> Result<String, String> {
+    if StrftimeItems::new(value).any(|item| item == Item::Error) {
+        return Err(format!("Invalid format '{value}'"));
     }
+    Ok(value.to_string())
 }
 
-impl Ord for MonthYear {
-    fn cmp(&self, rhs: &Self) -> Ordering {
-        self.year
-            .cmp(&rhs.year)
-            .then_with(|| (self.month as u8).cmp(&(rhs.month as u8)))
+fn parse_locale(value: &str) -> Result<chrono::Locale, String> {
+    chrono::Locale::try_from(value).map_err(|_| format!("Unknown locale '{value}'"))
+}
+
+/// Label of the month in the sheets
+fn month_label(month: &MonthYear, options: &XlsxArgs) -> String {
+    match &options.month_format {
+        Some(format) => month.label(format, options.month_locale),
+        None => month.to_string(),
     }
 }
 
-impl From<NaiveDate> for MonthYear {
-    fn from(date: NaiveDate) -> Self {
-        Self {
-            month: date.month(),
-            year: date.year(),
+fn parse_column_width(value: &str) -> Result<(String, f64), String> {
+    let (header, width) = value
+        .split_once('=')
+        .ok_or_else(|| format!("Expected HEADER=WIDTH, got '{value}'"))?;
+    let width = width
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid width '{width}'"))?;
+    Ok((header.trim().to_string(), width))
+}
+
+#[derive(clap::Args)]
+struct ServeArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Overlay two ranges of months in the graph, ie. "2023" and "2024" or
+    /// "2023-01..2023-06" and "2024-01..2024-06"
+    #[arg(long, num_args = 2, value_names = ["RANGE", "RANGE"])]
+    compare: Vec<MonthRange>,
+    /// Derive the prefix of the URL from this seed instead of the time, ie. for scripts and tests.
+    /// Only the URL is reproducible, not the data served.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum InternalTransfers {
+    /// Leave out both records, they cancel each other out
+    Net,
+    /// Add both records to the group "Internal Transfer"
+    Group,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum GroupConflicts {
+    /// Fail, listing the conflicting files
+    Error,
+    /// Use the rule of the file given last
+    LastWins,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    /// Write an xlsx report (report.xlsx by default) and print a detailed summary
+    Xlsx,
+    /// Print the aggregated data as JSON, or write it to a file with `--output`
+    Json,
+    /// Print a concise text report
+    Text,
+}
+
+/// Expands glob patterns, since not every shell does this for us
+fn expand_files(files: Vec<PathBuf>) -> Result<Vec<PathBuf>> {
+    if files.len() > 1 && files.iter().any(|file| is_stdin(file)) {
+        bail!("Reading from stdin ('-') can't be combined with other files");
+    }
+    let mut result = vec![];
+    for file in files {
+        let pattern = file.to_string_lossy().into_owned();
+        if url(&file).is_some() || !pattern.contains(['*', '?', '[']) {
+            result.push(file);
+            continue;
         }
+        let matches = glob::glob(&pattern)?.collect::<Result<Vec<_>, _>>()?;
+        if matches.is_empty() {
+            bail!("No files found matching '{}'", pattern);
+        }
+        info!("'{}' matched {} files", pattern, matches.len());
+        result.extend(matches);
     }
+    Ok(result)
 }
 
-#[derive(Parser)]
-struct Args {
-    /// CSV File to import
-    file: PathBuf,
-    /// Group mapping file
-    #[arg(short, long)]
-    groups: Option<PathBuf>,
-    /// Input CSV specification
-    #[arg(short = 'i', long, alias = "ff")]
-    file_format: PathBuf,
-    #[arg(short = 's', long)]
-    graph: bool,
+fn is_ofx(file: &Path) -> bool {
+    let name = file.to_string_lossy().to_ascii_lowercase();
+    // Ignore the query of URLs
+    let name = name.split('?').next().unwrap_or_default();
+    name.ends_with(".ofx") || name.ends_with(".qfx")
+}
+
+fn is_stdin(file: &Path) -> bool {
+    file == Path::new("-")
 }
 
-#[derive(Debug, Deserialize)]
-struct ImportConfig {
-    skip: Option<usize>,
-    date_format: String,
-    number_locale: Option<String>,
-    map: BTreeMap<String, String>,
+fn url(file: &Path) -> Option<&str> {
+    file.to_str()
+        .filter(|file| file.starts_with("http://") || file.starts_with("https://"))
 }
 
-#[derive(Debug, Deserialize, Default)]
-struct GroupConfig {
-    parties: BTreeMap<String, String>,
+/// Downloads a file, headers are given as "Name: Value"
+fn fetch(url: &str, headers: &[String]) -> Result<Vec<u8>> {
+    let mut request = reqwest::blocking::Client::new().get(url);
+    for header in headers {
+        let (name, value) = header
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Header '{}' is not of the form 'Name: Value'", header))?;
+        request = request.header(name.trim(), value.trim());
+    }
+    let response = request
+        .send()
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Fetching '{}'", url))?;
+    Ok(response
+        .bytes()
+        .with_context(|| format!("Fetching '{}'", url))?
+        .to_vec())
 }
 
-const CSV_DATE_FORMAT: &str = "%Y-%m-%d";
+/// Headers for fetching files given as URL, with the token from the keyring if configured and no
+/// "Authorization" header is given
+fn url_headers(headers: &[String], import_config: Option<&ImportConfig>) -> Result<Vec<String>> {
+    let mut headers = headers.to_vec();
+    let Some(name) = import_config.and_then(|config| config.keyring.as_ref()) else {
+        return Ok(headers);
+    };
+    let authorization = headers.iter().any(|header| {
+        header
+            .split_once(':')
+            .is_some_and(|(header, _)| header.trim().eq_ignore_ascii_case("authorization"))
+    });
+    if authorization {
+        info!(
+            "Using the Authorization header given instead of keyring entry '{}'",
+            name
+        );
+        return Ok(headers);
+    }
+    match keyring::Entry::new("spenben", name)?.get_password() {
+        Ok(token) => headers.push(format!("Authorization: Bearer {token}")),
+        Err(keyring::Error::NoEntry) => {
+            warn!(
+                "No keyring entry '{}' and no Authorization header given",
+                name
+            );
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("Reading keyring entry '{}'", name));
+        }
+    }
+    Ok(headers)
+}
 
-/// Format used for internal database (not yet implemented)
-#[derive(Debug, Deserialize, Serialize)]
-struct Record<'r> {
-    #[serde(serialize_with = "ser_date", deserialize_with = "deser_date")]
-    date: NaiveDate,
-    party1: &'r str,
-    party2: &'r str,
-    description: &'r str,
-    amount: f64,
+fn print_text(result: &Aggregate) {
+    let (income, expenses) = result.totals();
+    println!(
+        "Summary from {} to {} ({} days)",
+        result.start,
+        result.end,
+        result.days()
+    );
+    println!("Income   {:10.2}", income);
+    println!("Expenses {:10.2}", expenses);
+    println!("Net      {:10.2}", income + expenses);
+    println!("Savings  {}", percent(savings_rate((income, expenses))));
+    println!("{}", coverage_line(&result.coverage));
+    println!();
+    println!("Top expenses");
+    for (group, amount) in result
+        .stats_summary
+        .iter()
+        .filter(|(_, amount)| *amount < 0.0)
+        .take(10)
+    {
+        println!("{:10.2} {}", amount, group);
+    }
+    println!();
+    println!("Net per month");
+    let monthly_totals = result.monthly_totals();
+    for (month, amount) in result.monthly_net() {
+        let rate = monthly_totals.get(&month).copied().and_then(savings_rate);
+        println!("{month} {:10.2} {}", amount, percent(rate));
+    }
+    if !result.keywords.is_empty() {
+        println!();
+        println!("Keywords");
+        for keyword in &result.keywords {
+            println!(
+                "{:10.2} {:5}x {}",
+                keyword.amount, keyword.count, keyword.word
+            );
+        }
+    }
+    if !result.top_parties.is_empty() {
+        println!();
+        println!("Top parties");
+        for party in &result.top_parties {
+            println!("{:10.2} {:5}x {}", party.amount, party.count, party.party);
+        }
+    }
+    if !result.histogram.is_empty() {
+        println!();
+        println!("Histogram");
+        for band in &result.histogram {
+            println!(
+                "{:>12} {:5}x {:10.2} income {:5}x {:10.2} expenses",
+                band.to_string(),
+                band.income_count,
+                band.income,
+                band.expense_count,
+                band.expenses
+            );
+        }
+    }
 }
 
-fn ser_date<S>(date: &NaiveDate, s: S) -> Result<S::Ok, S::Error>
-where
-    S: Serializer,
-{
-    s.serialize_str(&date.format(&CSV_DATE_FORMAT).to_string())
+/// Describes how much was matched by the group rules
+fn coverage_line(coverage: &Coverage) -> String {
+    format!(
+        "Matched  {} of records, {} of the volume",
+        percent(coverage.by_count()),
+        percent(coverage.by_volume())
+    )
 }
 
-fn deser_date<'de, D>(d: D) -> Result<NaiveDate, D::Error>
-where
-    D: Deserializer<'de>,
-{
-    struct FieldVisitor;
-    use serde::de;
-    use std::fmt;
-    impl<'de> de::Visitor<'de> for FieldVisitor {
-        type Value = NaiveDate;
+/// Formats a share as percentage, blank if undefined
+fn percent(rate: Option<f64>) -> String {
+    rate.map(|rate| format!("{:9.1}%", rate * 100.0))
+        .unwrap_or_default()
+}
 
-        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-            formatter.write_str("YYYY-MM-DD")
+fn print_changes<K: Ord + Display>(old: BTreeMap<K, f64>, new: BTreeMap<K, f64>) {
+    let keys: BTreeSet<_> = old.keys().chain(new.keys()).collect();
+    for key in keys {
+        let old = old.get(key).copied().unwrap_or(0.0);
+        let new = new.get(key).copied().unwrap_or(0.0);
+        if (new - old).abs() >= 0.005 {
+            println!("{:10.2} -> {:10.2} ({:+10.2}) {}", old, new, new - old, key);
         }
+    }
+}
+
+/// Prints the changes per group and per month between two reports
+fn diff(old: &Path, new: &Path) -> Result<()> {
+    let old = read_report(old)?;
+    let new = read_report(new)?;
+    println!("Changed groups");
+    print_changes(
+        old.stats_summary.iter().cloned().collect(),
+        new.stats_summary.iter().cloned().collect(),
+    );
+    println!();
+    println!("Changed net per month");
+    print_changes(old.monthly_net(), new.monthly_net());
+    Ok(())
+}
+
+fn write_tidy_csv(result: &Aggregate, file: &Path, skip_zero: bool) -> Result<()> {
+    let mut writer = csv::Writer::from_path(file)?;
+    writer.write_record(["month", "group", "amount"])?;
+    let months = result
+        .stats_grouped
+        .first()
+        .map_or(0, |(_, values)| values.len());
+    for i in 0..months {
+        for (group, values) in &result.stats_grouped {
+            let (month, amount) = values[i];
+            if skip_zero && amount == 0.0 {
+                continue;
+            }
+            writer.write_record([
+                format!("{}-{:02}", month.year, month.month),
+                group.clone(),
+                amount.to_string(),
+            ])?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Writes the xlsx report, with a set of sheets for each currency if reporting per currency
+fn write_xlsx(results: Vec<(String, Aggregate)>, options: &XlsxArgs, file: &Path) -> Result<()> {
+    let metadata = results
+        .first()
+        .and_then(|(_, result)| result.metadata.clone());
+    let mut workbook = Workbook::new();
+    if let Some(title) = &options.title {
+        workbook.set_properties(&DocProperties::new().set_title(title));
+    }
+    for (currency, result) in results {
+        if !currency.is_empty() {
+            summary!(options.quiet, "Currency {currency}");
+        }
+        add_sheets(&mut workbook, &currency, result, options)?;
+    }
+    if let Some(metadata) = metadata {
+        let worksheet = workbook.add_worksheet().set_name("Info")?;
+        worksheet.write_row(0, 0, ["Generated", metadata.generated.as_str()])?;
+        worksheet.write_row(1, 0, ["Tool version", env!("CARGO_PKG_VERSION")])?;
+        worksheet.write_row(
+            2,
+            0,
+            ["Format", metadata.format.as_deref().unwrap_or("OFX/QFX")],
+        )?;
+        for (row, file) in (3..).zip(&metadata.files) {
+            worksheet.write_row(row, 0, [if row == 3 { "Files" } else { "" }, file.as_str()])?;
+        }
+        worksheet.autofit();
+    }
+    workbook.save(file)?;
+    Ok(())
+}
+
+/// Format of amounts in euro unless left out, negative ones in red
+fn amount_format(options: &XlsxArgs, decimals: bool) -> Format {
+    let mut number = if decimals { "#,##0.00" } else { "#,##0" }.to_string();
+    if !options.no_currency_symbol {
+        number.push_str(" [$€]");
+    }
+    Format::new().set_num_format(format!("{number};[RED]-{number}"))
+}
 
-        fn visit_str<E>(self, value: &str) -> Result<NaiveDate, E>
-        where
-            E: de::Error,
+/// Fits the columns to their content, except for those with a configured width
+fn fit_columns(worksheet: &mut Worksheet, headers: &[&str], options: &XlsxArgs) -> Result<()> {
+    worksheet.autofit();
+    for (column, header) in (0..).zip(headers) {
+        if let Some((_, width)) = options
+            .column_width
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(header))
         {
-            NaiveDate::parse_from_str(value, &CSV_DATE_FORMAT).map_err(|e| de::Error::custom(e))
+            worksheet.set_column_width(column, *width)?;
         }
     }
-    d.deserialize_str(FieldVisitor)
+    Ok(())
 }
 
-fn import(file: PathBuf, config: ImportConfig, mut taker: impl FnMut(Record) -> ()) -> Result<()> {
-    let date_format = &config.date_format;
-    let number_locale = config
-        .number_locale
-        .map(|locale| Locale::from_name(locale))
-        .transpose()?
-        .unwrap_or(Locale::en);
-
-    let rdr = ReaderBuilder::new()
-        .delimiter(b';')
-        .flexible(true)
-        .has_headers(false)
-        .from_path(file)?;
-    let records = rdr.into_byte_records();
-    let mut records = records.skip(config.skip.unwrap_or(0));
-    let header = records.next().ok_or(anyhow!(""))??;
-    let field_matchers: Vec<_> = config
-        .map
-        .iter()
-        .flat_map(|(regex, field)| Regex::new(regex).map(|regex| (regex, field)))
-        .collect();
-    let headers: Vec<_> = header
+fn add_sheets(
+    workbook: &mut Workbook,
+    currency: &str,
+    result: Aggregate,
+    options: &XlsxArgs,
+) -> Result<()> {
+    let sheet_name = |name: &str| {
+        if currency.is_empty() {
+            name.to_string()
+        } else {
+            format!("{name} {currency}")
+        }
+    };
+    let worksheet = workbook.add_worksheet().set_name(sheet_name("Summary"))?;
+    let currency_format = amount_format(options, options.round_display != Some(0));
+    let highlight_format = Format::new().set_bold().set_font_color(XlsxColor::Red);
+    let highlight_amount_format = currency_format
+        .clone()
+        .set_bold()
+        .set_font_color(XlsxColor::Red);
+    let default_format = Format::new();
+    let large_groups: BTreeSet<_> = result
+        .large_transactions
         .iter()
-        .enumerate()
-        .flat_map(|(i, hdr)| {
-            field_matchers
-                .iter()
-                .find(|(regex, _)| regex.is_match(&String::from_utf8_lossy(hdr)))
-                .map(|(_, field)| (i, field))
-        })
+        .map(|transaction| transaction.group.as_str())
         .collect();
-    if headers.len() != config.map.len() {
-        eprintln!(
-            "Headers configured: {:?}, headers actually found: {:?}",
-            config.map.keys().collect::<Vec<_>>(),
-            headers
+    let group_format = |group: &str| {
+        if large_groups.contains(group) {
+            &highlight_format
+        } else {
+            &default_format
+        }
+    };
+    let month_format = Format::new()
+        .set_bold()
+        .set_font_color(XlsxColor::Blue)
+        .set_font_size(20);
+    worksheet.set_column_format(0, &currency_format)?;
+
+    let days = result.days();
+    let month_factor = if options.no_normalize {
+        None
+    } else {
+        result.month_factor(options.min_days)
+    };
+    let monthly_format = if options.round_monthly {
+        amount_format(options, false)
+    } else {
+        currency_format.clone()
+    };
+    if month_factor.is_some() {
+        worksheet.set_column_format(1, &monthly_format)?;
+    } else if !options.no_normalize {
+        warn!(
+            "Only {} days of data, omitting unreliable figures per month (see --min-days)",
+            days
         );
     }
-    eprintln!("{headers:?}");
-    for result in records {
-        let result = result?;
-        let mut date = None;
-        let mut party1 = None;
-        let mut party2 = None;
-        let mut amount = None;
-        let mut description = "".to_string();
-        for (index, field) in headers.iter() {
-            let value = encoding_rs::UTF_8
-                .decode_without_bom_handling(
-                    result
-                        .get(*index)
-                        .ok_or_else(|| anyhow!("Not enough data columns"))?,
-                )
-                .0;
-            match field.as_str() {
-                "date" => {
-                    date = Some(
-                        NaiveDate::parse_from_str(&value, &date_format).with_context(|| {
-                            format!(
-                                "Parsing '{}' at '{:?}' - is the format '{:?}' correct?",
-                                value,
-                                result.position(),
-                                date_format
-                            )
-                        })?,
-                    )
-                }
-                "party1" => party1 = Some(value.to_string()),
-                "party2" => party2 = Some(value.to_string()),
-                "amount" => {
-                    let x = value
-                        .split_once(number_locale.decimal())
-                        .unwrap_or_else(|| (&value, "0"));
-                    let int = x.0;
-                    let fract = x.1.split_once(' ').map(|(r, _)| r).unwrap_or(x.1);
-                    let mut result =
-                        int.parse_formatted::<_, i64>(&number_locale)
-                            .with_context(|| {
-                                format!("Parsing '{}' at {:?}", value, result.position())
-                            })? as f64;
-                    result += fract.parse::<u64>()? as f64 * 10.0_f64.powf(-(fract.len() as f64));
-                    amount = Some(result)
-                }
-                "description" => description = value.to_string(),
-                "party" => {
-                    party1 = Some(value.to_string());
-                    party2 = Some(value.to_string());
+    summary!(
+        options.quiet,
+        "Summary of spending and revenue from {} to {} ({} days)",
+        result.start,
+        result.end,
+        days
+    );
+    // The period becomes the subtitle below a given title
+    let mut header_row = 0;
+    if let Some(title) = &options.title {
+        worksheet.write_string_with_format(header_row, 0, title, &month_format)?;
+        header_row += 1;
+    }
+    worksheet.write_string(
+        header_row,
+        0,
+        &format!(
+            "Summary of spending and revenue from {} to {} ({} days)",
+            result.start, result.end, days
+        ),
+    )?;
+    header_row += 1;
+    let columns = if month_factor.is_some() {
+        &["Amount", "Per month", "Group", "Notes", "Volatility"][..]
+    } else {
+        &["Amount", "Group", "Notes", "Volatility"][..]
+    };
+    let volatility_column = columns.len() as u16 - 1;
+    worksheet.set_column_format(volatility_column, &currency_format)?;
+    worksheet.write_row(header_row, 0, columns.iter().copied())?;
+    let volatility: BTreeMap<_, _> = result.volatility.iter().cloned().collect();
+    let mut row = header_row + 1;
+    for (group, amount) in &result.stats_summary {
+        worksheet.write_number(row, 0, *amount)?;
+        if let Some(month_factor) = month_factor {
+            let per_month = if options.round_monthly {
+                (amount * month_factor).round()
+            } else {
+                amount * month_factor
+            };
+            summary!(
+                options.quiet,
+                "{:10.2} ({:10.2} / month) {}",
+                amount,
+                per_month,
+                group
+            );
+            worksheet.write_number_with_format(row, 1, per_month, &monthly_format)?;
+            worksheet.write_string_with_format(row, 2, group, group_format(group))?;
+        } else {
+            summary!(options.quiet, "{:10.2} {}", amount, group);
+            worksheet.write_string_with_format(row, 1, group, group_format(group))?;
+        }
+        if let Some(notes) = result.notes.get(group) {
+            let column = if month_factor.is_some() { 3 } else { 2 };
+            worksheet.write_string(row, column, notes)?;
+        }
+        if let Some(volatility) = volatility.get(group) {
+            worksheet.write_number(row, volatility_column, *volatility)?;
+        }
+        row += 1;
+    }
+    if !options.no_freeze {
+        worksheet.set_freeze_panes(header_row + 1, 0)?;
+        worksheet.autofilter(header_row, 0, row - 1, columns.len() as u16 - 1)?;
+    }
+    fit_columns(worksheet, columns, options)?;
+    let worksheet = workbook
+        .add_worksheet()
+        .set_name(sheet_name("Savings Rate"))?;
+    let percent_format = Format::new().set_num_format("0.0%");
+    worksheet.set_column_format(1, &currency_format)?;
+    worksheet.set_column_format(2, &currency_format)?;
+    worksheet.set_column_format(3, &percent_format)?;
+    worksheet.write_row(0, 0, ["Month", "Income", "Expenses", "Savings rate"])?;
+    let totals = result.totals();
+    let monthly_totals = result.monthly_totals();
+    summary!(
+        options.quiet,
+        "Savings rate {}",
+        percent(savings_rate(totals))
+    );
+    summary!(options.quiet, "{}", coverage_line(&result.coverage));
+    summary!(options.quiet);
+    let rows = std::iter::once(("Total".to_string(), totals)).chain(
+        monthly_totals
+            .into_iter()
+            .map(|(month, totals)| (month_label(&month, options), totals)),
+    );
+    for (row, (period, totals)) in (1..).zip(rows) {
+        worksheet.write_string(row, 0, &period)?;
+        worksheet.write_number(row, 1, totals.0)?;
+        worksheet.write_number(row, 2, totals.1)?;
+        // Left blank without income
+        if let Some(rate) = savings_rate(totals) {
+            worksheet.write_number(row, 3, rate)?;
+        }
+    }
+    if !options.no_freeze {
+        worksheet.set_freeze_panes(1, 0)?;
+    }
+    fit_columns(
+        worksheet,
+        &["Month", "Income", "Expenses", "Savings rate"],
+        options,
+    )?;
+    let worksheet = workbook
+        .add_worksheet()
+        .set_name(sheet_name("Monthly Summary"))?;
+    worksheet.set_column_format(0, &currency_format)?;
+    worksheet.set_column_format(2, &percent_format)?;
+    worksheet.write_row(0, 0, ["Amount", "Group", "Expenses / income"])?;
+    row = 1;
+    let monthly_net = result.monthly_net();
+    let monthly_totals = result.monthly_totals();
+    for (index, (month, groups)) in result.stats_monthly.iter().enumerate() {
+        worksheet.write_string_with_format(row, 0, &month_label(month, options), &month_format)?;
+        worksheet.set_row_height(row, 24)?;
+        // Left blank without income
+        let ratio = monthly_totals.get(month).copied().and_then(expense_ratio);
+        if let Some(ratio) = ratio {
+            worksheet.write_number(row, 2, ratio)?;
+        }
+        row += 1;
+        if options.compact {
+            let top: Vec<_> = groups
+                .iter()
+                .filter(|(_, a)| *a < 0.0)
+                .take(2)
+                .map(|(group, amount)| format!("{group} {amount:.2}"))
+                .collect();
+            summary!(
+                options.quiet,
+                "{month} {:10.2} {}",
+                monthly_net.get(month).copied().unwrap_or(0.0),
+                top.join(", ")
+            );
+        } else if let Some(ratio) = ratio {
+            summary!(
+                options.quiet,
+                "{month} (expenses {:.1}% of income)",
+                ratio * 100.0
+            );
+        } else {
+            summary!(options.quiet, "{month}");
+        }
+        let lines: Vec<_> = if options.all_groups {
+            // The same groups in the same order every month
+            result
+                .stats_grouped
+                .iter()
+                .map(|(group, values)| (group, values[index].1))
+                .collect()
+        } else {
+            let expenses = groups.iter().filter(|(_, a)| *a < 0.0);
+            let income = groups.iter().filter(|(_, a)| *a >= 0.0);
+            expenses
+                .chain(income)
+                .map(|(group, amount)| (group, *amount))
+                .collect()
+        };
+        for (group, amount) in lines {
+            if !options.compact {
+                summary!(options.quiet, "{:10.2} {}", amount, group);
+            }
+            worksheet.write_number(row, 0, amount)?;
+            worksheet.write_string(row, 1, group)?;
+            row += 1;
+        }
+        if !options.compact {
+            summary!(options.quiet);
+        }
+        row += 1;
+    }
+    if !options.no_freeze {
+        worksheet.set_freeze_panes(1, 0)?;
+        worksheet.autofilter(0, 0, row - 1, 1)?;
+    }
+    fit_columns(worksheet, &["Amount", "Group"], options)?;
+    if !result.keywords.is_empty() {
+        let worksheet = workbook.add_worksheet().set_name(sheet_name("Keywords"))?;
+        worksheet.set_column_format(0, &currency_format)?;
+        worksheet.write_row(0, 0, ["Amount", "Count", "Keyword"])?;
+        summary!(options.quiet, "Keywords");
+        for (row, keyword) in (1..).zip(&result.keywords) {
+            summary!(
+                options.quiet,
+                "{:10.2} {:5}x {}",
+                keyword.amount,
+                keyword.count,
+                keyword.word
+            );
+            worksheet.write_number(row, 0, keyword.amount)?;
+            worksheet.write_number(row, 1, keyword.count as f64)?;
+            worksheet.write_string(row, 2, &keyword.word)?;
+        }
+        summary!(options.quiet);
+        fit_columns(worksheet, &["Amount", "Count", "Keyword"], options)?;
+    }
+    if !result.top_transactions.is_empty() {
+        let worksheet = workbook
+            .add_worksheet()
+            .set_name(sheet_name("Top Transactions"))?;
+        worksheet.set_column_format(1, &currency_format)?;
+        worksheet.write_row(
+            0,
+            0,
+            [
+                "Date",
+                "Amount",
+                "Group",
+                "Party",
+                "Description",
+                "Memo",
+                "Line",
+            ],
+        )?;
+        row = 1;
+        for (month, transactions) in &result.top_transactions {
+            worksheet.write_string_with_format(
+                row,
+                0,
+                month_label(month, options),
+                &month_format,
+            )?;
+            worksheet.set_row_height(row, 24)?;
+            row += 1;
+            summary!(options.quiet, "Largest in {month}");
+            for transaction in transactions {
+                summary!(
+                    options.quiet,
+                    "{} {:10.2} {} ({})",
+                    transaction.date,
+                    transaction.amount,
+                    transaction.party,
+                    transaction.group
+                );
+                let (text_format, amount_format) =
+                    if result.large_transactions.contains(transaction) {
+                        (&highlight_format, &highlight_amount_format)
+                    } else {
+                        (&default_format, &currency_format)
+                    };
+                let date = transaction.date.to_string();
+                worksheet.write_string_with_format(row, 0, &date, text_format)?;
+                worksheet.write_number_with_format(row, 1, transaction.amount, amount_format)?;
+                worksheet.write_string_with_format(row, 2, &transaction.group, text_format)?;
+                worksheet.write_string_with_format(row, 3, &transaction.party, text_format)?;
+                worksheet.write_string_with_format(
+                    row,
+                    4,
+                    &transaction.description,
+                    text_format,
+                )?;
+                worksheet.write_string_with_format(row, 5, &transaction.memo, text_format)?;
+                if let Some(line) = transaction.row {
+                    worksheet.write_number_with_format(row, 6, line as f64, text_format)?;
                 }
-                _ => unreachable!("Field '{}' does not exist", field),
+                row += 1;
             }
+            summary!(options.quiet);
+            row += 1;
         }
-        let Some(date) = date else {
-            bail!("Date missing in '{:?}'", result)
-        };
-        let Some(party1) = &party1 else {
-            bail!("Party 1 missing")
-        };
-        let Some(party2) = &party2 else {
-            bail!("Party 2 missing")
-        };
-        let Some(amount) = amount else {
-            bail!("Amount missing")
-        };
-        let record = Record {
-            date,
-            party1,
-            party2,
-            amount,
-            description: &description,
-        };
-        taker(record);
-        // eprintln!("{record:?}");
+        if !options.no_freeze {
+            worksheet.set_freeze_panes(1, 0)?;
+        }
+        fit_columns(
+            worksheet,
+            &[
+                "Date",
+                "Amount",
+                "Group",
+                "Party",
+                "Description",
+                "Memo",
+                "Line",
+            ],
+            options,
+        )?;
+    }
+    if !result.top_parties.is_empty() {
+        let worksheet = workbook
+            .add_worksheet()
+            .set_name(sheet_name("Top Parties"))?;
+        worksheet.set_column_format(0, &currency_format)?;
+        worksheet.write_row(0, 0, ["Amount", "Count", "Party"])?;
+        summary!(options.quiet, "Top parties");
+        for (row, party) in (1..).zip(&result.top_parties) {
+            summary!(
+                options.quiet,
+                "{:10.2} {:5}x {}",
+                party.amount,
+                party.count,
+                party.party
+            );
+            worksheet.write_number(row, 0, party.amount)?;
+            worksheet.write_number(row, 1, party.count as f64)?;
+            worksheet.write_string(row, 2, &party.party)?;
+        }
+        summary!(options.quiet);
+        if !options.no_freeze {
+            worksheet.set_freeze_panes(1, 0)?;
+        }
+        fit_columns(worksheet, &["Amount", "Count", "Party"], options)?;
+    }
+    // Shows whether the rules keep up, best if it stays empty
+    if result
+        .uncategorized
+        .iter()
+        .any(|(_, amount)| *amount != 0.0)
+    {
+        let worksheet = workbook
+            .add_worksheet()
+            .set_name(sheet_name("Uncategorized"))?;
+        worksheet.set_column_format(1, &currency_format)?;
+        worksheet.write_row(0, 0, ["Month", "Amount"])?;
+        for (row, (month, amount)) in (1..).zip(&result.uncategorized) {
+            worksheet.write_string(row, 0, month_label(month, options))?;
+            worksheet.write_number(row, 1, *amount)?;
+        }
+        if !options.no_freeze {
+            worksheet.set_freeze_panes(1, 0)?;
+        }
+        fit_columns(worksheet, &["Month", "Amount"], options)?;
+    }
+    if !result.histogram.is_empty() {
+        let worksheet = workbook.add_worksheet().set_name(sheet_name("Histogram"))?;
+        worksheet.set_column_format(2, &currency_format)?;
+        worksheet.set_column_format(4, &currency_format)?;
+        worksheet.write_row(
+            0,
+            0,
+            [
+                "Size",
+                "Income count",
+                "Income",
+                "Expense count",
+                "Expenses",
+            ],
+        )?;
+        summary!(options.quiet, "Histogram");
+        for (row, band) in (1..).zip(&result.histogram) {
+            summary!(
+                options.quiet,
+                "{:>12} {:5}x {:10.2} income {:5}x {:10.2} expenses",
+                band.to_string(),
+                band.income_count,
+                band.income,
+                band.expense_count,
+                band.expenses
+            );
+            worksheet.write_string(row, 0, band.to_string())?;
+            worksheet.write_number(row, 1, band.income_count as f64)?;
+            worksheet.write_number(row, 2, band.income)?;
+            worksheet.write_number(row, 3, band.expense_count as f64)?;
+            worksheet.write_number(row, 4, band.expenses)?;
+        }
+        summary!(options.quiet);
+        fit_columns(
+            worksheet,
+            &[
+                "Size",
+                "Income count",
+                "Income",
+                "Expense count",
+                "Expenses",
+            ],
+            options,
+        )?;
+    }
+    if options.heatmap && !result.stats_grouped.is_empty() {
+        let worksheet = workbook.add_worksheet().set_name(sheet_name("Heatmap"))?;
+        let months: Vec<_> = result.stats_grouped[0]
+            .1
+            .iter()
+            .map(|(month, _)| month_label(month, options))
+            .collect();
+        worksheet.write_string(0, 0, "Group")?;
+        worksheet.write_row(0, 1, months.iter().map(String::as_str))?;
+        for (row, (group, values)) in (1..).zip(&result.stats_grouped) {
+            worksheet.write_string(row, 0, group)?;
+            for (column, (_, amount)) in (1..).zip(values) {
+                worksheet.write_number_with_format(row, column, *amount, &currency_format)?;
+            }
+        }
+        // Expenses in red, income in green, the larger the darker
+        let color_scale = ConditionalFormat3ColorScale::new()
+            .set_minimum_color(XlsxColor::Red)
+            .set_midpoint(ConditionalFormatType::Number, 0)
+            .set_midpoint_color(XlsxColor::White)
+            .set_maximum_color(XlsxColor::Green);
+        worksheet.add_conditional_format(
+            1,
+            1,
+            result.stats_grouped.len() as u32,
+            months.len() as u16,
+            &color_scale,
+        )?;
+        if !options.no_freeze {
+            worksheet.set_freeze_panes(1, 1)?;
+        }
+        fit_columns(worksheet, &["Group"], options)?;
+    }
+    if !result.budgets.is_empty() {
+        let worksheet = workbook.add_worksheet().set_name(sheet_name("Budget"))?;
+        worksheet.set_column_format(1, &currency_format)?;
+        worksheet.set_column_format(2, &currency_format)?;
+        worksheet.set_column_format(3, &currency_format)?;
+        row = 0;
+        for (group, months) in result.budgets {
+            worksheet.write_string_with_format(row, 0, &group, &month_format)?;
+            worksheet.set_row_height(row, 24)?;
+            row += 1;
+            worksheet.write_row(row, 0, ["Month", "Available", "Spent", "Remaining"])?;
+            row += 1;
+            summary!(options.quiet, "Budget {group}");
+            for month in months {
+                summary!(
+                    options.quiet,
+                    "{} {:10.2} available {:10.2} spent {:10.2} remaining",
+                    month.month,
+                    month.available,
+                    month.spent,
+                    month.available - month.spent
+                );
+                worksheet.write_string(row, 0, month_label(&month.month, options))?;
+                worksheet.write_number(row, 1, month.available)?;
+                worksheet.write_number(row, 2, month.spent)?;
+                worksheet.write_number(row, 3, month.available - month.spent)?;
+                row += 1;
+            }
+            summary!(options.quiet);
+            row += 1;
+        }
+        fit_columns(
+            worksheet,
+            &["Month", "Available", "Spent", "Remaining"],
+            options,
+        )?;
     }
     Ok(())
 }
 
-#[derive(Serialize)]
-struct Aggregate {
-    start: NaiveDate,
-    end: NaiveDate,
-    stats_summary: Vec<(String, f64)>,
-    stats_monthly: Vec<(MonthYear, Vec<(String, f64)>)>,
-    stats_grouped: Vec<(String, Vec<(MonthYear, f64)>)>,
+/// Reuses the prefix of a previous run with the same input files, so open tabs keep working.
+/// It is cached per set of input files in the temp directory (ie. /tmp/spenben-prefix-<hash>),
+/// delete it to get a new one. A given seed always results in the same prefix, without caching.
+fn graph_prefix(files: &[PathBuf], seed: Option<u64>) -> Result<String> {
+    if let Some(seed) = seed {
+        return Ok(oorandom::Rand64::new(seed.into()).rand_u64().to_string());
+    }
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    files.hash(&mut hasher);
+    let cache = std::env::temp_dir().join(format!("spenben-prefix-{:x}", hasher.finish()));
+    if let Ok(prefix) = std::fs::read_to_string(&cache) {
+        let prefix = prefix.trim();
+        if !prefix.is_empty() {
+            return Ok(prefix.to_string());
+        }
+    }
+    let mut rng = oorandom::Rand64::new(std::time::UNIX_EPOCH.elapsed()?.as_nanos());
+    let prefix = rng.rand_u64().to_string();
+    if let Err(e) = std::fs::write(&cache, &prefix) {
+        warn!(
+            "Could not cache graph prefix in '{}': {}",
+            cache.display(),
+            e
+        );
+    }
+    Ok(prefix)
 }
 
-struct Groups {
-    group_matchers: Vec<(Regex, String)>,
-    stats_summary: AHashMap<String, f64>,
-    stats_monthly: AHashMap<MonthYear, AHashMap<String, f64>>,
-    start: NaiveDate,
-    end: NaiveDate,
+fn init_logging(json: bool, quiet: bool) {
+    let filter = if quiet {
+        EnvFilter::new("error")
+    } else {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"))
+    };
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr);
+    if json {
+        subscriber.json().init();
+    } else {
+        subscriber.with_target(false).without_time().init();
+    }
 }
 
-impl Groups {
-    fn new(config: GroupConfig) -> Result<Self> {
-        let group_matchers = config
-            .parties
-            .iter()
-            .flat_map(|(regex, group)| Regex::new(regex).map(|regex| (regex, group.clone())))
-            .collect();
-        Ok(Self {
-            stats_summary: AHashMap::new(),
-            stats_monthly: AHashMap::new(),
-            group_matchers,
-            start: NaiveDate::MAX,
-            end: NaiveDate::MIN,
+fn load_configs(input: &InputArgs) -> Result<(Option<ImportConfig>, GroupConfig)> {
+    if let Some(config) = &input.config {
+        let config: CombinedConfig = toml::from_str(std::str::from_utf8(&std::fs::read(config)?)?)?;
+        return Ok((Some(config.import), config.groups));
+    }
+    let import_config = input
+        .file_format
+        .as_ref()
+        .map::<anyhow::Result<ImportConfig>, _>(|f| {
+            Ok(toml::from_str(std::str::from_utf8(&std::fs::read(f)?)?)?)
         })
+        .transpose()?;
+    let read_groups = |f: &PathBuf| -> Result<GroupConfig> {
+        if f.extension().is_some_and(|extension| extension == "csv") {
+            return read_group_csv(f);
+        }
+        Ok(toml::from_str(std::str::from_utf8(&std::fs::read(f)?)?)?)
+    };
+    let Some((first, rest)) = input.groups.split_first() else {
+        return Ok((import_config, GroupConfig::default()));
+    };
+    let mut group_config = read_groups(first)?;
+    // Remember where each rule came from, to report conflicts
+    let mut provenance: BTreeMap<String, &PathBuf> = group_config
+        .parties
+        .keys()
+        .map(|pattern| (pattern.clone(), first))
+        .collect();
+    for file in rest {
+        let config = read_groups(file)?;
+        for (pattern, rule) in config.parties {
+            match group_config.parties.get(&pattern) {
+                Some(existing) if *existing != rule => {
+                    let message = format!(
+                        "'{}' maps '{}' to {:?}, but '{}' maps it to {:?}",
+                        provenance[&pattern].display(),
+                        pattern,
+                        existing,
+                        file.display(),
+                        rule
+                    );
+                    match input.group_conflicts {
+                        GroupConflicts::Error => bail!("Conflicting group rules: {message}"),
+                        GroupConflicts::LastWins => warn!("Using the latter: {message}"),
+                    }
+                }
+                _ => (),
+            }
+            provenance.insert(pattern.clone(), file);
+            group_config.parties.insert(pattern, rule);
+        }
+        group_config.settings.extend(config.settings);
     }
+    Ok((import_config, group_config))
+}
 
-    fn push(&mut self, record: Record<'_>) {
-        let mut hit = true;
-        let key = if record.amount < 0.0 {
-            record.party2.to_string().to_lowercase()
-        } else {
-            record.party1.to_string().to_lowercase()
-        };
-        let key = self
-            .group_matchers
+/// Describes the run from the command line, to be recorded in the report
+fn metadata(input: &InputArgs) -> Metadata {
+    Metadata {
+        files: input
+            .files
             .iter()
-            .find(|(regex, _)| regex.is_match(&key))
-            .map(|(_, group)| group)
-            .unwrap_or_else(|| {
-                hit = false;
-                &key
-            });
-        *self.stats_summary.entry(key.clone()).or_insert_with(|| {
-            if !hit {
-                eprintln!("No group mapping found for '{}'", key);
+            .map(|file| file.display().to_string())
+            .collect(),
+        format: input
+            .config
+            .as_ref()
+            .or(input.file_format.as_ref())
+            .map(|file| file.display().to_string()),
+        generated: chrono::Local::now().to_rfc3339(),
+    }
+}
+
+/// Result of importing all input files
+struct Imported {
+    files: Vec<PathBuf>,
+    /// Aggregates per currency. Unless reporting per currency, this is a single aggregate with an
+    /// empty currency.
+    results: Vec<(String, Aggregate)>,
+    /// Records seen by this and previous runs, with `since_last`
+    seen: Option<Dedup>,
+    /// The amounts are in a currency without minor unit
+    whole_amounts: bool,
+}
+
+/// The groups to add a record to, per currency or the only ones
+fn select_groups<'g>(
+    groups: &'g mut Groups,
+    groups_per_currency: &'g mut BTreeMap<String, Groups>,
+    per_currency: bool,
+    record: &Record<'_>,
+) -> &'g mut Groups {
+    if !per_currency {
+        return groups;
+    }
+    let currency = if record.currency.is_empty() {
+        "unknown"
+    } else {
+        record.currency
+    };
+    groups_per_currency
+        .entry(currency.to_string())
+        .or_insert_with(|| groups.clone())
+}
+
+/// Imports all files, leaving out records seen by a previous run if a `since_last` file is given
+/// and writing the remaining records to the `export_jsonl` file, if given. The records seen are
+/// returned with the results, to be saved once the report is written.
+fn import_all(
+    input: InputArgs,
+    per_currency: bool,
+    analysis: &AnalysisOptions,
+    since_last: Option<&Path>,
+    export_jsonl: Option<&Path>,
+) -> Result<Imported> {
+    if let (Some(from), Some(to)) = (input.from, input.to) {
+        if from > to || (from == to && input.exclusive_end) {
+            bail!("The period --from {} --to {} is empty", from, to);
+        }
+    }
+    let (import_config, group_config) = load_configs(&input)?;
+    // Validated once here, not for each file the column patterns are used for
+    let invalid_parties = invalid_patterns(group_config.parties.keys());
+    let invalid_columns: Vec<_> = import_config
+        .iter()
+        .flat_map(|config| invalid_patterns(config.map.keys()))
+        .collect();
+    if input.strict && !(invalid_parties.is_empty() && invalid_columns.is_empty()) {
+        let invalid: Vec<_> = invalid_parties.into_iter().chain(invalid_columns).collect();
+        bail!("Invalid patterns: {}", invalid.join(", "));
+    }
+    for invalid in invalid_parties {
+        warn!("Skipping invalid party pattern {invalid}");
+    }
+    for invalid in invalid_columns {
+        warn!("Skipping invalid column pattern {invalid}");
+    }
+    let mut groups = Groups::new(group_config, analysis)?;
+    let last = if input.exclusive_end {
+        input.to.and_then(|to| to.pred_opt())
+    } else {
+        input.to
+    };
+    groups.set_period(input.from, last);
+    let period = DateRange {
+        from: input.from.unwrap_or(chrono::NaiveDate::MIN),
+        to: last.unwrap_or(chrono::NaiveDate::MAX),
+    };
+    let mut groups_per_currency = BTreeMap::new();
+    let mut dedup = input
+        .dedup
+        .then(|| Dedup::default().with_tolerance(input.dedup_tolerance));
+    let mut seen = since_last
+        .map(Dedup::load)
+        .transpose()?
+        .map(|seen| seen.with_tolerance(input.dedup_tolerance));
+    let mut export = export_jsonl
+        .map(|file| {
+            File::create(file)
+                .map(BufWriter::new)
+                .with_context(|| format!("Creating '{}'", file.display()))
+        })
+        .transpose()?;
+    let mut export_error = None;
+    let mut excluded = 0;
+    let mut outside = 0;
+    let files = expand_files(input.files)?;
+    let headers = if files.iter().any(|file| url(file).is_some()) {
+        url_headers(&input.headers, import_config.as_ref())?
+    } else {
+        vec![]
+    };
+    // Records of each file, if they need to be matched across files before grouping
+    let mut accounts = vec![];
+    for file in &files {
+        let mut buffered = vec![];
+        let taker = |it: Record<'_>| {
+            if let Some(dedup) = &mut dedup {
+                if dedup.is_duplicate(&it) {
+                    return;
+                }
             }
-            0.0
-        }) += record.amount;
-        *self
-            .stats_monthly
-            .entry(record.date.into())
-            .or_insert_with(AHashMap::new)
-            .entry(key.clone())
-            .or_insert(0.0) += record.amount;
-        self.start = self.start.min(record.date);
-        self.end = self.end.max(record.date);
-    }
-
-    fn aggregate(self) -> Result<Aggregate> {
-        let mut stats_summary: Vec<_> = self.stats_summary.into_iter().collect();
-        stats_summary.sort_by_key(|(_, amount)| ordered_float::OrderedFloat(*amount));
-
-        let mut stats_monthly: Vec<_> = self
-            .stats_monthly
-            .iter()
-            .map(|(m_y, e)| {
-                let mut entries: Vec<_> = e.clone().into_iter().collect();
-                entries.sort_by_key(|(_, amount)| ordered_float::OrderedFloat(-amount.abs()));
-                entries.truncate(20);
+            if let Some(seen) = &mut seen {
+                if seen.is_duplicate(&it) {
+                    return;
+                }
+            }
+            if !period.contains(it.date) {
+                outside += 1;
+                return;
+            }
+            if input
+                .exclude_range
+                .iter()
+                .any(|range| range.contains(it.date))
+            {
+                excluded += 1;
+                return;
+            }
+            if let Some(export) = &mut export {
+                let written = serde_json::to_writer(&mut *export, &it)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|_| Ok(writeln!(export)?));
+                if let Err(error) = written {
+                    export_error.get_or_insert(error);
+                }
+            }
+            if input.internal_transfers.is_some() {
+                buffered.push(OwnedRecord::from(&it));
+            } else {
+                let groups =
+                    select_groups(&mut groups, &mut groups_per_currency, per_currency, &it);
+                groups.push(it);
+            }
+        };
+        let content = match url(file) {
+            Some(url) => fetch(url, &headers)?,
+            None if is_stdin(file) => {
+                let mut content = vec![];
+                std::io::stdin()
+                    .read_to_end(&mut content)
+                    .context("Reading stdin")?;
+                content
+            }
+            None => std::fs::read(file).with_context(|| format!("Reading '{}'", file.display()))?,
+        };
+        if is_ofx(file) {
+            import_ofx(&content, &file.display().to_string(), taker)?;
+        } else {
+            let import_config = import_config.as_ref().ok_or_else(|| {
+                anyhow!("A file format is required to import '{}'", file.display())
+            })?;
+            import(
+                &content[..],
+                import_config,
+                input.strict_headers || input.strict,
+                taker,
+            )?;
+        }
+        if let Some(dedup) = &mut dedup {
+            dedup.finish_file();
+        }
+        accounts.push(buffered);
+    }
+    if let Some(error) = export_error {
+        return Err(error.context("Writing the JSON Lines export"));
+    }
+    if let Some(export) = &mut export {
+        export.flush()?;
+    }
+    if let Some(internal_transfers) = input.internal_transfers {
+        let matched = match_transfers(&accounts);
+        let count = matched.iter().flatten().filter(|m| **m).count();
+        info!("Matched {} internal transfer pairs", count / 2);
+        for (record, matched) in accounts.iter().flatten().zip(matched.iter().flatten()) {
+            let record = record.as_record();
+            let groups =
+                select_groups(&mut groups, &mut groups_per_currency, per_currency, &record);
+            match (*matched, internal_transfers) {
+                (true, InternalTransfers::Net) => (),
+                (true, InternalTransfers::Group) => groups.push_to(record, "Internal Transfer"),
+                (false, _) => groups.push(record),
+            }
+        }
+    }
+    if let Some(dedup) = &dedup {
+        info!("Skipped {} duplicate records", dedup.duplicates);
+    }
+    if input.from.is_some() || input.to.is_some() {
+        info!("Left out {} records outside of the period", outside);
+    }
+    if !input.exclude_range.is_empty() {
+        info!("Excluded {} records within the excluded ranges", excluded);
+    }
+    if let Some(seen) = &seen {
+        info!(
+            "Skipped {} records seen by the previous run",
+            seen.duplicates
+        );
+    }
+    // Without any records, an empty report instead of none at all
+    let mut results = if per_currency && !groups_per_currency.is_empty() {
+        groups_per_currency
+            .into_iter()
+            .map(|(currency, groups)| Ok((currency, groups.aggregate()?)))
+            .collect::<Result<Vec<_>>>()?
+    } else {
+        vec![(String::new(), groups.aggregate()?)]
+    };
+    for (_, result) in &mut results {
+        result.exclude(&input.exclude_range);
+    }
+    Ok(Imported {
+        files,
+        results,
+        seen,
+        whole_amounts: import_config.is_some_and(|config| config.minor_units == Some(0)),
+    })
+}
 
-                (*m_y, entries)
-            })
+fn report(mut args: ReportArgs, metadata: Metadata) -> Result<()> {
+    let formats = if args.json {
+        vec![OutputFormat::Json]
+    } else {
+        args.format
+    };
+    // The summaries printed for the other formats would mix with the JSON
+    if args.output.is_none()
+        && formats.contains(&OutputFormat::Json)
+        && formats.iter().any(|format| *format != OutputFormat::Json)
+    {
+        bail!("--output is required to write JSON along with other formats");
+    }
+    let Imported {
+        mut results,
+        seen,
+        whole_amounts,
+        ..
+    } = import_all(
+        args.input,
+        args.per_currency,
+        &AnalysisOptions::from(&args.analysis),
+        args.since_last.as_deref(),
+        args.export_jsonl.as_deref(),
+    )?;
+    for (_, result) in &mut results {
+        result.metadata = Some(metadata.clone());
+    }
+    if whole_amounts {
+        args.xlsx.round_display.get_or_insert(0);
+    }
+    if let Some(tidy_csv) = &args.tidy_csv {
+        write_tidy_csv(&results[0].1, tidy_csv, args.tidy_skip_zero)?;
+    }
+    let output_file = |extension: &str| {
+        let mut file = args
+            .output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("report"))
+            .into_os_string();
+        file.push(".");
+        file.push(extension);
+        PathBuf::from(file)
+    };
+    // Per currency, the reports are keyed by currency
+    let json = if args.per_currency {
+        let by_currency: BTreeMap<_, _> = results
+            .iter()
+            .map(|(currency, result)| (currency, result))
             .collect();
-        stats_monthly.sort_by_key(|(m_y, _)| *m_y);
-        let stats_grouped: Vec<_> = stats_summary
+        to_json(&by_currency, args.pretty)?
+    } else {
+        to_json(&results[0].1, args.pretty)?
+    };
+    if formats.contains(&OutputFormat::Json) {
+        if args.output.is_some() {
+            std::fs::write(output_file("json"), &json)?;
+        } else {
+            println!("{json}");
+        }
+    }
+    // After writing the JSON, which keeps the full precision
+    if let Some(places) = args.xlsx.round_display {
+        for (_, result) in &mut results {
+            result.round(places);
+        }
+    }
+    if formats.contains(&OutputFormat::Text) {
+        for (currency, result) in &results {
+            if !currency.is_empty() {
+                println!("Currency {currency}");
+            }
+            print_text(result);
+            println!();
+        }
+    }
+    // Last, as the sheets consume the results
+    if formats.contains(&OutputFormat::Xlsx) {
+        write_xlsx(results, &args.xlsx, &output_file("xlsx"))?;
+    }
+    if let Some(command) = &args.exec {
+        exec(command, &json)?;
+    }
+    // Only now, so a failed run reports the same records again
+    if let (Some(mut seen), Some(file)) = (seen, &args.since_last) {
+        seen.save(file)?;
+    }
+    Ok(())
+}
+
+fn to_json(value: &impl serde::Serialize, pretty: bool) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+/// Runs a command through the shell with the JSON report on its stdin. Its output is passed
+/// through and a non-zero exit code fails the run.
+fn exec(command: &str, json: &str) -> Result<()> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = std::process::Command::new(shell)
+        .args([flag, command])
+        .stdin(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Running '{}'", command))?;
+    // Ignoring write errors, the command might not be interested in the report at all
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(json.as_bytes());
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("'{}' failed with {}", command, status);
+    }
+    Ok(())
+}
+
+fn serve(args: ServeArgs) -> Result<()> {
+    let Imported { files, results, .. } =
+        import_all(args.input, false, &AnalysisOptions::default(), None, None)?;
+    let result = &results[0].1;
+    let rt = Runtime::new()?;
+    let prefix = graph_prefix(&files, args.seed)?;
+    println!("Hosting web server on http://127.0.0.1:3030/{}/", prefix);
+    rt.block_on(async {
+        let mut data = serde_json::to_value(result)?;
+        let series: Vec<_> = args
+            .compare
             .iter()
-            .map(|(g, _)| {
-                let values: Vec<_> = self
-                    .stats_monthly
-                    .iter()
-                    .map(|(m_y, v)| (*m_y, v.get(g).cloned().unwrap_or(0.0)))
-                    .collect();
-                (g.clone(), values)
-            })
+            .map(|range| result.series(range))
             .collect();
+        data["compare"] = serde_json::to_value(series)?;
+        let data = data.to_string();
+        let data = warp::path!("data.json").map(move || data.clone());
+        let html = warp::path::end().map(|| warp::reply::html(include_str!("../res/index.html")));
+        let content = warp::path(prefix).and(html.or(data));
+        let pure_css = warp::path!("pure-min.css").map(|| include_str!("../res/pure-min.css"));
+        let chart_js = warp::path!("chart.js").map(|| include_str!("../res/chart.js"));
+        let (_, server) = warp::serve(content.or(pure_css).or(chart_js))
+            .bind_with_graceful_shutdown(([127, 0, 0, 1], 3030), async {
+                // Without a handler, the process would just be killed
+                if let Err(e) = tokio::signal::ctrl_c().await {
+                    warn!("Could not listen for Ctrl-C: {}", e);
+                    std::future::pending::<()>().await;
+                }
+                info!("Shutting down");
+            });
+        server.await;
+        Ok::<(), anyhow::Error>(())
+    })?;
+    Ok(())
+}
 
-        Ok(Aggregate {
-            start: self.start,
-            end: self.end,
-            stats_summary,
-            stats_monthly,
-            stats_grouped,
-        })
+fn init(sample: &Path, output: Option<&Path>) -> Result<()> {
+    let content =
+        std::fs::read(sample).with_context(|| format!("Reading '{}'", sample.display()))?;
+    let format = scaffold_format(&content)?;
+    match output {
+        Some(output) => std::fs::write(output, format)?,
+        None => print!("{format}"),
     }
+    Ok(())
+}
+
+/// Imports everything like for a report, problems are logged along the way
+fn validate(input: InputArgs, quiet: bool) -> Result<()> {
+    let Imported { files, results, .. } =
+        import_all(input, false, &AnalysisOptions::default(), None, None)?;
+    let result = &results[0].1;
+    summary!(
+        quiet,
+        "Imported {} files from {} to {} into {} groups",
+        files.len(),
+        result.start,
+        result.end,
+        result.stats_summary.len()
+    );
+    Ok(())
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
+    init_logging(args.log_json, args.quiet);
+    let quiet = args.quiet;
     // let mut wtr = csv::WriterBuilder::new().from_path("tmp.csv")?;
     // wtr.serialize(Record {
     //     date: Date::from_iso_week_date(2022, 5, Wednesday)?,
@@ -350,101 +1667,37 @@ fn main() -> Result<()> {
     // })?;
     // wtr.flush()?;
 
-    let import_config: Result<ImportConfig, _> =
-        toml::from_str(std::str::from_utf8(&std::fs::read(args.file_format)?)?);
-    let import_config = import_config?;
-    let group_config: GroupConfig = args
-        .groups
-        .map::<anyhow::Result<GroupConfig>, _>(|f| {
-            Ok(toml::from_str(std::str::from_utf8(&std::fs::read(f)?)?)?)
-        })
-        .transpose()?
-        .unwrap_or_else(|| GroupConfig::default());
-    let mut groups = Groups::new(group_config)?;
-    import(args.file, import_config, |it| groups.push(it))?;
-    let result = groups.aggregate()?;
-    if args.graph {
-        let rt = Runtime::new()?;
-        let mut rng = oorandom::Rand64::new(std::time::UNIX_EPOCH.elapsed()?.as_nanos());
-        let prefix = rng.rand_u64().to_string();
-        println!("Hosting web server on http://127.0.0.1:3030/{}/", prefix);
-        rt.block_on(async {
-            let data = serde_json::to_string(&result)?;
-            let data = warp::path!("data.json").map(move || data.clone());
-            let html =
-                warp::path::end().map(|| warp::reply::html(include_str!("../res/index.html")));
-            let content = warp::path(prefix).and(html.or(data));
-            let pure_css = warp::path!("pure-min.css").map(|| include_str!("../res/pure-min.css"));
-            let chart_js = warp::path!("chart.js").map(|| include_str!("../res/chart.js"));
-            warp::serve(content.or(pure_css).or(chart_js))
-                .run(([127, 0, 0, 1], 3030))
-                .await;
-            Ok::<(), anyhow::Error>(())
-        })?;
-    } else {
-        let mut workbook = Workbook::new();
-        let worksheet = workbook.add_worksheet().set_name("Summary")?;
-        let currency_format = Format::new().set_num_format("#,##0.00 [$€];[RED]-#,##0.00 [$€]");
-        let month_format = Format::new()
-            .set_bold()
-            .set_font_color(XlsxColor::Blue)
-            .set_font_size(20);
-        worksheet.set_column_format(0, &currency_format)?;
-        worksheet.set_column_format(1, &currency_format)?;
-
-        let days = (result.end - result.start).num_days();
-        let month_factor = 30.0 / days as f64;
-        println!(
-            "Summary of spending and revenue from {} to {} ({} days)",
-            result.start, result.end, days
-        );
-        worksheet.write_string(
-            0,
-            0,
-            &format!(
-                "Summary of spending and revenue from {} to {} ({} days)",
-                result.start, result.end, days
-            ),
-        )?;
-        let mut row = 1;
-        for (group, amount) in result.stats_summary {
-            println!(
-                "{:10.2} ({:10.2} / month) {}",
-                amount,
-                amount * month_factor,
-                group
-            );
-            worksheet.write_number(row, 0, amount)?;
-            worksheet.write_number(row, 1, amount * month_factor)?;
-            worksheet.write_string(row, 2, &group)?;
-            row += 1;
+    match args
+        .command
+        .unwrap_or(Command::Report(Box::new(args.report)))
+    {
+        Command::Report(mut args) => {
+            args.xlsx.quiet = quiet;
+            let metadata = metadata(&args.input);
+            report(*args, metadata)
         }
-        worksheet.autofit();
-        let worksheet = workbook.add_worksheet().set_name("Monthly Summary")?;
-        worksheet.set_column_format(0, &currency_format)?;
-        row = 0;
-        for (month, groups) in result.stats_monthly {
-            worksheet.write_string_with_format(row, 0, &month.to_string(), &month_format)?;
-            worksheet.set_row_height(row, 24)?;
-            row += 1;
-            println!("{month}");
-            for (group, amount) in groups.iter().filter(|(_, a)| *a < 0.0) {
-                println!("{:10.2} {}", amount, group);
-                worksheet.write_number(row, 0, *amount)?;
-                worksheet.write_string(row, 1, group)?;
-                row += 1;
-            }
-            for (group, amount) in groups.iter().filter(|(_, a)| *a >= 0.0) {
-                println!("{:10.2} {}", amount, group);
-                worksheet.write_number(row, 0, *amount)?;
-                worksheet.write_string(row, 1, group)?;
-                row += 1;
-            }
-            println!();
-            row += 1;
-        }
-        worksheet.autofit();
-        workbook.save("report.xlsx")?;
+        Command::Serve(args) => serve(args),
+        Command::Validate(args) => validate(args, quiet),
+        Command::Diff { old, new } => diff(&old, &new),
+        Command::Init { sample, output } => init(&sample, output.as_deref()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorization_header_takes_precedence() {
+        let config: ImportConfig = toml::from_str(
+            r#"
+            date_format = "%d.%m.%Y"
+            keyring = "bank"
+            [map]
+            "#,
+        )
+        .unwrap();
+        let headers = vec!["authorization: Bearer token".to_string()];
+        assert_eq!(url_headers(&headers, Some(&config)).unwrap(), headers);
     }
-    Ok(())
 }
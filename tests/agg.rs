@@ -0,0 +1,55 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+fn aggregate(agg: &str, amounts: [f64; 4]) -> Vec<(String, f64)> {
+    let config = toml::from_str(&format!(
+        r#"
+        [parties]
+        "bank" = "Balance"
+        [settings."Balance"]
+        agg = "{agg}"
+        "#
+    ))
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (day, amount) in [3, 1, 2, 15].into_iter().zip(amounts) {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+            "Bank",
+            amount,
+        ));
+    }
+    groups.aggregate().unwrap().stats_summary
+}
+
+#[test]
+fn per_group_aggregation() {
+    let amounts = [300.0, 100.0, 500.0, 200.0];
+    assert_eq!(
+        aggregate("sum", amounts),
+        vec![("Balance".to_string(), 1100.0)]
+    );
+    assert_eq!(
+        aggregate("avg", amounts),
+        vec![("Balance".to_string(), 275.0)]
+    );
+    assert_eq!(
+        aggregate("last", amounts),
+        vec![("Balance".to_string(), 200.0)]
+    );
+    assert_eq!(
+        aggregate("max", amounts),
+        vec![("Balance".to_string(), 500.0)]
+    );
+}
+
+#[test]
+fn largest_expense() {
+    let amounts = [-300.0, -100.0, -500.0, 20.0];
+    assert_eq!(
+        aggregate("max", amounts),
+        vec![("Balance".to_string(), -500.0)]
+    );
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+#[test]
+fn rules_restricted_to_amount_range() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "" = { group = "Large", sign = "negative", min = 1000.0 }
+        "shop" = { group = "Small", max = 10.0 }
+        "shop|employer" = "Other"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, amount) in [
+        ("Shop", -5.0),
+        ("Shop", -50.0),
+        ("Shop", -1500.0),
+        ("Employer", 2000.0),
+    ] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let mut summary = groups.aggregate().unwrap().stats_summary;
+    summary.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        summary,
+        vec![
+            ("Large".to_string(), -1500.0),
+            ("Other".to_string(), 1950.0),
+            ("Small".to_string(), -5.0),
+        ]
+    );
+}
@@ -0,0 +1,186 @@
+use num_format::Locale;
+use spenben::{import, parse_amount, parse_whole_amount, ImportConfig};
+
+fn parse(value: &str, locale: &Locale, symbols: &[&str]) -> f64 {
+    let symbols: Vec<_> = symbols.iter().map(|s| s.to_string()).collect();
+    parse_amount(value, locale, &symbols).unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{actual} is not {expected}"
+    );
+}
+
+#[test]
+fn locales() {
+    assert_close(parse("1.234,56", &Locale::de, &[]), 1234.56);
+    assert_close(parse("-1.234,56", &Locale::de, &[]), -1234.56);
+    assert_close(parse("1,234.56", &Locale::en, &[]), 1234.56);
+    assert_close(parse("-0,50", &Locale::de, &[]), -0.5);
+    assert_close(parse("12", &Locale::en, &[]), 12.0);
+}
+
+#[test]
+fn fractions() {
+    assert_close(parse("12,05", &Locale::de, &[]), 12.05);
+    assert_close(parse("12,5", &Locale::de, &[]), 12.5);
+    assert_close(parse("12,50", &Locale::de, &[]), 12.5);
+    assert_close(parse("-0,05", &Locale::de, &[]), -0.05);
+    assert_close(parse("12,", &Locale::de, &[]), 12.0);
+}
+
+#[test]
+fn currency_after_non_breaking_space() {
+    assert_close(parse("12,34\u{a0}EUR", &Locale::de, &[]), 12.34);
+}
+
+#[test]
+fn currency_symbols() {
+    assert_close(parse("12,50 €", &Locale::de, &["€"]), 12.5);
+    assert_close(parse("€12,50", &Locale::de, &["€"]), 12.5);
+    assert_close(parse("EUR -3,25", &Locale::de, &["€", "EUR"]), -3.25);
+    assert!(parse_amount("12,50 €", &Locale::de, &[]).is_err());
+}
+
+#[test]
+fn parentheses() {
+    assert_close(parse("(1.234,56)", &Locale::de, &[]), -1234.56);
+    assert_close(parse("($19.25)", &Locale::en, &["$"]), -19.25);
+    assert_close(parse("(0.50)", &Locale::en, &[]), -0.5);
+}
+
+#[test]
+fn plus_sign() {
+    assert_close(parse("+12,50", &Locale::de, &[]), 12.5);
+    assert_close(parse(" + 1.200,00 ", &Locale::de, &[]), 1200.0);
+    assert_close(parse("+1,200.00 USD", &Locale::en, &["USD"]), 1200.0);
+}
+
+#[test]
+fn scientific_notation() {
+    let import_amount = |scientific: bool| {
+        let config: ImportConfig = toml::from_str(&format!(
+            r#"
+            date_format = "%Y-%m-%d"
+            scientific_amounts = {scientific}
+            [map]
+            "Date" = "date"
+            "Party" = "party"
+            "Amount" = "amount"
+            "#
+        ))
+        .unwrap();
+        let mut amount = None;
+        import(
+            "Date;Party;Amount\n2024-01-01;Shop;1.2E3\n".as_bytes(),
+            &config,
+            false,
+            |record| amount = Some(record.amount),
+        )
+        .map(|_| amount.unwrap())
+    };
+    assert_close(import_amount(true).unwrap(), 1200.0);
+    assert!(import_amount(false).is_err());
+}
+
+#[test]
+fn suffixed_amounts() {
+    let import_amounts = |suffixed: bool| {
+        let config: ImportConfig = toml::from_str(&format!(
+            r#"
+            date_format = "%Y-%m-%d"
+            suffixed_amounts = {suffixed}
+            [map]
+            "Date" = "date"
+            "Party" = "party"
+            "Amount" = "amount"
+            "#
+        ))
+        .unwrap();
+        let mut amounts = vec![];
+        import(
+            "Date;Party;Amount\n2024-01-01;Shop;1.2k\n2024-01-02;Shop;-3M\n2024-01-03;Shop;12.50\n"
+                .as_bytes(),
+            &config,
+            false,
+            |record| amounts.push(record.amount),
+        )
+        .map(|_| amounts)
+    };
+    let amounts = import_amounts(true).unwrap();
+    assert_close(amounts[0], 1200.0);
+    assert_close(amounts[1], -3_000_000.0);
+    assert_close(amounts[2], 12.5);
+    assert!(import_amounts(false).is_err());
+}
+
+#[test]
+fn split_amount_columns() {
+    let import_amounts = |map: &str| {
+        let config: ImportConfig = toml::from_str(&format!(
+            r#"
+            date_format = "%Y-%m-%d"
+            [map]
+            "Date" = "date"
+            "Party" = "party"
+            {map}
+            "#
+        ))
+        .unwrap();
+        let mut amounts = vec![];
+        import(
+            "Date;Party;Euro;Cent\n2024-01-01;Shop;-12;5\n2024-01-02;Shop;-0;50\n2024-01-03;Shop;1,200;\n"
+                .as_bytes(),
+            &config,
+            false,
+            |record| amounts.push(record.amount),
+        )
+        .map(|_| amounts)
+    };
+    let amounts = import_amounts("\"Euro\" = \"amount_int\"\n\"Cent\" = \"amount_cents\"").unwrap();
+    assert_close(amounts[0], -12.05);
+    assert_close(amounts[1], -0.5);
+    assert_close(amounts[2], 1200.0);
+    assert!(import_amounts("\"Euro\" = \"amount_int\"").is_err());
+}
+
+#[test]
+fn without_minor_unit() {
+    let parse = |value: &str, locale: &Locale, symbols: &[&str]| {
+        let symbols: Vec<_> = symbols.iter().map(|s| s.to_string()).collect();
+        parse_whole_amount(value, locale, &symbols).unwrap()
+    };
+    assert_close(parse("1.234", &Locale::en, &[]), 1234.0);
+    assert_close(parse("-1,234,567", &Locale::en, &[]), -1234567.0);
+    assert_close(parse("1.234 JPY", &Locale::de, &["JPY"]), 1234.0);
+    assert!(parse_whole_amount("1.234,56", &Locale::de, &[]).is_err());
+    assert!(parse_whole_amount("1234.5", &Locale::en, &[]).is_err());
+}
+
+#[test]
+fn decimal_part_without_minor_unit() {
+    let config: ImportConfig = toml::from_str(
+        r#"
+        date_format = "%Y-%m-%d"
+        number_locale = "de"
+        minor_units = 0
+        [map]
+        "Date" = "date"
+        "Party" = "party"
+        "Amount" = "amount"
+        "#,
+    )
+    .unwrap();
+    let error = import(
+        "Date;Party;Amount\n2024-01-01;Shop;1.234,56\n".as_bytes(),
+        &config,
+        false,
+        |_| (),
+    )
+    .unwrap_err();
+    let error = format!("{error:#}");
+    assert!(error.contains("line 2"), "{error}");
+    assert!(error.contains("decimal part"), "{error}");
+}
@@ -0,0 +1,13 @@
+use chrono::NaiveDate;
+use spenben::Record;
+
+/// A record of the party on the given day, with everything else left empty
+pub fn record(date: NaiveDate, party: &str, amount: f64) -> Record<'_> {
+    Record {
+        date,
+        party1: party,
+        party2: party,
+        amount,
+        ..Default::default()
+    }
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups, MonthRange};
+
+#[test]
+fn series_of_month_ranges() {
+    assert!("2024-03..2024-01".parse::<MonthRange>().is_err());
+    assert!("2024-13..2024-14".parse::<MonthRange>().is_err());
+    assert_eq!(
+        "2023".parse::<MonthRange>().unwrap().to_string(),
+        "2023-01..2023-12"
+    );
+
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "shop" = "Shop"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (year, month, amount) in [(2023, 1, -10.0), (2023, 2, -20.0), (2024, 1, -30.0)] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(year, month, 1).unwrap(),
+            "Shop",
+            amount,
+        ));
+    }
+    let result = groups.aggregate().unwrap();
+    let series = result.series(&"2023-01..2023-02".parse().unwrap());
+    assert_eq!(series.label, "2023-01..2023-02");
+    assert_eq!(series.months.len(), 2);
+    assert_eq!(
+        series.groups,
+        vec![("Shop".to_string(), vec![-10.0, -20.0])]
+    );
+    let series = result.series(&"2024".parse().unwrap());
+    assert_eq!(series.groups, vec![("Shop".to_string(), vec![-30.0])]);
+}
@@ -0,0 +1,29 @@
+use spenben::{import, ImportConfig};
+
+fn import_with_offset(offset: i64) -> anyhow::Result<()> {
+    let config: ImportConfig = toml::from_str(&format!(
+        r#"
+        date_format = "%Y-%m-%d"
+        date_offset = {offset}
+        [map]
+        "Date" = "date"
+        "Party" = "party"
+        "Amount" = "amount"
+        "#
+    ))
+    .unwrap();
+    import(
+        "Date;Party;Amount\n2024-01-01;Shop;-1\n".as_bytes(),
+        &config,
+        false,
+        |_| (),
+    )
+}
+
+#[test]
+fn offsets_out_of_range_are_errors() {
+    assert!(import_with_offset(-1).is_ok());
+    assert!(import_with_offset(i64::MAX).is_err());
+    let error = import_with_offset(190_000_000).unwrap_err().to_string();
+    assert!(error.contains("line 2"), "{error}");
+}
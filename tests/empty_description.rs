@@ -0,0 +1,40 @@
+use spenben::{import, ImportConfig};
+
+fn descriptions(empty_description: &str) -> Vec<String> {
+    let config: ImportConfig = toml::from_str(&format!(
+        r#"
+        date_format = "%Y-%m-%d"
+        {empty_description}
+        [map]
+        "Date" = "date"
+        "Payer" = "party1"
+        "Payee" = "party2"
+        "Memo" = "description"
+        "Amount" = "amount"
+        "#
+    ))
+    .unwrap();
+    let mut descriptions = vec![];
+    import(
+        "Date;Payer;Payee;Memo;Amount\n2024-01-01;Me;Shop;;-5\n2024-01-02;Employer;Me;;100\n2024-01-03;Me;Cafe;Coffee;-3\n"
+            .as_bytes(),
+        &config,
+        false,
+        |record| descriptions.push(record.description.to_string()),
+    )
+    .unwrap();
+    descriptions
+}
+
+#[test]
+fn placeholder_for_empty_descriptions() {
+    assert_eq!(descriptions(""), ["", "", "Coffee"]);
+    assert_eq!(
+        descriptions(r#"empty_description = "party""#),
+        ["Shop", "Employer", "Coffee"]
+    );
+    assert_eq!(
+        descriptions(r#"empty_description = { text = "-" }"#),
+        ["-", "-", "Coffee"]
+    );
+}
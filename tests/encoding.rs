@@ -0,0 +1,49 @@
+use spenben::{import, ImportConfig};
+
+fn parties(content: &[u8], encoding: &str) -> Vec<(String, f64)> {
+    let config: ImportConfig = toml::from_str(&format!(
+        r#"
+        date_format = "%d.%m.%Y"
+        number_locale = "de"
+        {encoding}
+        [map]
+        "Buchungstag" = "date"
+        "Empfänger" = "party"
+        "Betrag" = "amount"
+        "#
+    ))
+    .unwrap();
+    let mut parties = vec![];
+    import(content, &config, true, |record| {
+        parties.push((record.party1.to_string(), record.amount))
+    })
+    .unwrap();
+    parties
+}
+
+const SAMPLE: &str = "Buchungstag;Empfänger;Betrag\n02.01.2024;Bäckerei Müller;-4,50\n";
+
+#[test]
+fn utf16_with_byte_order_mark() {
+    let mut content = vec![0xff, 0xfe];
+    content.extend(SAMPLE.encode_utf16().flat_map(u16::to_le_bytes));
+    let expected = vec![("Bäckerei Müller".to_string(), -4.5)];
+    assert_eq!(parties(&content, ""), expected);
+
+    let mut content = vec![0xfe, 0xff];
+    content.extend(SAMPLE.encode_utf16().flat_map(u16::to_be_bytes));
+    assert_eq!(parties(&content, ""), expected);
+
+    // Without a byte order mark, the encoding has to be configured
+    let content: Vec<_> = SAMPLE.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    assert_eq!(parties(&content, r#"encoding = "utf-16le""#), expected);
+}
+
+#[test]
+fn windows_1252() {
+    let (content, _, _) = encoding_rs::WINDOWS_1252.encode(SAMPLE);
+    assert_eq!(
+        parties(&content, r#"encoding = "windows-1252""#),
+        vec![("Bäckerei Müller".to_string(), -4.5)]
+    );
+}
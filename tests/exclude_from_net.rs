@@ -0,0 +1,33 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+#[test]
+fn excluded_groups_are_listed_but_not_in_net() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "hotel" = "Work"
+        "shop" = "Shop"
+        [settings."Work"]
+        exclude_from_net = true
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, amount) in [("Hotel", -300.0), ("Shop", -20.0)] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let result = groups.aggregate().unwrap();
+    assert_eq!(result.stats_summary.len(), 2);
+    assert_eq!(result.totals(), (0.0, -20.0));
+    assert_eq!(
+        result.monthly_net().into_values().collect::<Vec<_>>(),
+        vec![-20.0]
+    );
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, DateRange, GroupConfig, Groups};
+
+#[test]
+fn inclusive_range_of_days() {
+    let range: DateRange = "2024-07-01:2024-07-21".parse().unwrap();
+    let day = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+    assert!(!range.contains(day(6, 30)));
+    assert!(range.contains(day(7, 1)));
+    assert!(range.contains(day(7, 21)));
+    assert!(!range.contains(day(7, 22)));
+    assert!("2024-07-21:2024-07-01".parse::<DateRange>().is_err());
+    assert!("2024-07-01".parse::<DateRange>().is_err());
+}
+
+#[test]
+fn excluded_days_within_the_period() {
+    let day = |month, day| NaiveDate::from_ymd_opt(2024, month, day).unwrap();
+    let mut groups = Groups::new(GroupConfig::default(), &AnalysisOptions::default()).unwrap();
+    groups.push(common::record(day(7, 1), "Shop", -10.0));
+    groups.push(common::record(day(7, 31), "Shop", -10.0));
+    let mut result = groups.aggregate().unwrap();
+    // Overlapping and partly before the first record, so 1st to 10th
+    let ranges =
+        ["2024-06-25:2024-07-05", "2024-07-03:2024-07-10"].map(|range| range.parse().unwrap());
+    result.exclude(&ranges);
+    assert_eq!(result.excluded_days, 10);
+    assert_eq!(result.days(), 21);
+}
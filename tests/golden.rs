@@ -0,0 +1,54 @@
+//! Imports the sample statements in `tests/data` and compares the aggregates with the expected
+//! JSON next to them. Run with `UPDATE_GOLDEN=1` to rewrite the expected files after an intended
+//! change, and review the diff.
+
+use spenben::*;
+use std::path::Path;
+
+fn check(case: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/data")
+        .join(case);
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    let import_config: ImportConfig = toml::from_str(&read("format.toml")).unwrap();
+    let group_config: GroupConfig = toml::from_str(&read("groups.toml")).unwrap();
+    let mut groups = Groups::new(group_config, &AnalysisOptions::default()).unwrap();
+    import(
+        read("statement.csv").as_bytes(),
+        &import_config,
+        false,
+        |record| groups.push(record),
+    )
+    .unwrap();
+    let mut actual = serde_json::to_value(groups.aggregate().unwrap()).unwrap();
+    // Changes with every release
+    actual.as_object_mut().unwrap().remove("tool_version");
+    let expected_file = dir.join("expected.json");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let json = serde_json::to_string_pretty(&actual).unwrap();
+        std::fs::write(&expected_file, json + "\n").unwrap();
+    }
+    let expected: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(expected_file).unwrap()).unwrap();
+    assert_eq!(actual, expected, "{case}");
+}
+
+/// German locale with thousands separators, a preamble and a combined party column
+#[test]
+fn dkb() {
+    check("dkb");
+}
+
+/// English locale with currency symbols, accounting style negatives and unmatched parties
+/// grouped by their first word into "Other"
+#[test]
+fn usd() {
+    check("usd");
+}
+
+/// Credit card statement with inverted amounts, shifted dates, padded short rows and empty
+/// parties falling back to the description
+#[test]
+fn card() {
+    check("card");
+}
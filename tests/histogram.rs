@@ -0,0 +1,42 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, GroupConfig, Groups};
+
+#[test]
+fn bands_by_absolute_amount() {
+    let analysis = AnalysisOptions {
+        histogram: true,
+        bands: vec![50.0, 10.0],
+        ..Default::default()
+    };
+    let mut groups = Groups::new(GroupConfig::default(), &analysis).unwrap();
+    for amount in [-5.0, -10.0, -49.5, 20.0, -300.0, 1000.0] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            "someone",
+            amount,
+        ));
+    }
+    let histogram = groups.aggregate().unwrap().histogram;
+    let bands: Vec<_> = histogram
+        .iter()
+        .map(|band| {
+            (
+                band.to_string(),
+                band.income_count,
+                band.income,
+                band.expense_count,
+                band.expenses,
+            )
+        })
+        .collect();
+    assert_eq!(
+        bands,
+        vec![
+            ("0-10".to_string(), 0, 0.0, 1, -5.0),
+            ("10-50".to_string(), 1, 20.0, 2, -59.5),
+            ("50+".to_string(), 1, 1000.0, 1, -300.0),
+        ]
+    );
+}
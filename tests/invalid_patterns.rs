@@ -0,0 +1,10 @@
+use spenben::invalid_patterns;
+
+#[test]
+fn reports_each_invalid_pattern() {
+    let patterns = ["shop.*", "(unclosed", "bakery", "[z-a]"].map(String::from);
+    let invalid = invalid_patterns(&patterns);
+    assert_eq!(invalid.len(), 2);
+    assert!(invalid[0].starts_with("'(unclosed': "));
+    assert!(invalid[1].starts_with("'[z-a]': "));
+}
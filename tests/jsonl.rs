@@ -0,0 +1,49 @@
+use chrono::NaiveDate;
+use spenben::Record;
+
+#[test]
+fn record_as_json_line() {
+    let record = Record {
+        date: NaiveDate::from_ymd_opt(2024, 3, 5).unwrap(),
+        party1: "Me",
+        party2: "Shop",
+        description: "Groceries",
+        amount: -12.5,
+        currency: "EUR",
+        ..Default::default()
+    };
+    assert_eq!(
+        serde_json::to_string(&record).unwrap(),
+        r#"{"date":"2024-03-05","party1":"Me","party2":"Shop","description":"Groceries","memo":"","amount":-12.5,"currency":"EUR","txn_id":null}"#
+    );
+}
+
+#[test]
+fn row_numbers_in_json_line() {
+    let import_rows = |row_numbers: bool| {
+        let config: spenben::ImportConfig = toml::from_str(&format!(
+            r#"
+            date_format = "%Y-%m-%d"
+            row_numbers = {row_numbers}
+            [map]
+            "Date" = "date"
+            "Party" = "party"
+            "Amount" = "amount"
+            "#
+        ))
+        .unwrap();
+        let mut lines = vec![];
+        spenben::import(
+            "Date;Party;Amount\n2024-01-01;Shop;-1\n2024-01-02;Bakery;-2\n".as_bytes(),
+            &config,
+            false,
+            |record| lines.push(serde_json::to_string(&record).unwrap()),
+        )
+        .unwrap();
+        lines
+    };
+    let lines = import_rows(true);
+    assert!(lines[0].ends_with(r#""row":2}"#), "{}", lines[0]);
+    assert!(lines[1].ends_with(r#""row":3}"#), "{}", lines[1]);
+    assert!(!import_rows(false)[0].contains("row"));
+}
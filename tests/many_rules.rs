@@ -0,0 +1,100 @@
+mod common;
+
+use chrono::NaiveDate;
+use regex::{Regex, RegexSet};
+use spenben::{AnalysisOptions, GroupConfig, Groups, PartyRule};
+use std::time::Instant;
+
+/// 500 rules, of which several match the same parties. The first matching rule in key order wins,
+/// also when matching all patterns at once.
+#[test]
+fn first_of_many_rules_wins() {
+    let mut config = GroupConfig::default();
+    for i in 0..500 {
+        config.parties.insert(
+            format!("shop {i:03}"),
+            PartyRule::Group(format!("Shop {i}")),
+        );
+    }
+    config.parties.insert(
+        "x*shop".to_string(),
+        PartyRule::Group("Any Shop".to_string()),
+    );
+    config.parties.insert(
+        "shop 1".to_string(),
+        PartyRule::Detailed {
+            group: "Large".to_string(),
+            sign: Default::default(),
+            min: Some(100.0),
+            max: None,
+            use_match: false,
+        },
+    );
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for i in 0..10_000 {
+        let party = format!("Shop {:03}", i % 500);
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            &party,
+            if i == 123 { -200.0 } else { -1.0 },
+        ));
+    }
+    groups.push(common::record(
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        "Shop Other",
+        -5.0,
+    ));
+    let result = groups.aggregate().unwrap();
+    let sum = |group: &str| {
+        result
+            .stats_summary
+            .iter()
+            .find(|(g, _)| g == group)
+            .map(|(_, amount)| *amount)
+    };
+    // "shop 1" sorts before "shop 123", but only matches the large record
+    assert_eq!(sum("Large"), Some(-200.0));
+    assert_eq!(sum("Shop 123"), Some(-19.0));
+    assert_eq!(sum("Shop 0"), Some(-20.0));
+    // "x*shop" sorts last, matching only parties without a rule of their own
+    assert_eq!(sum("Any Shop"), Some(-5.0));
+    assert_eq!(result.stats_summary.len(), 502);
+}
+
+/// Times matching 500 patterns in one pass with a [`RegexSet`] against trying them one after
+/// another, as the rules were matched before. Run with
+/// `cargo test --release --test many_rules -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn regex_set_against_linear_scan() {
+    let patterns: Vec<_> = (0..500).map(|i| format!("shop {i:03}$")).collect();
+    // Some parties match no rule, which is the worst case for the linear scan
+    let parties: Vec<_> = (0..100_000)
+        .map(|i| format!("shop {:03}", (i * 7) % 600))
+        .collect();
+
+    let start = Instant::now();
+    let regexes: Vec<_> = patterns.iter().map(|p| Regex::new(p).unwrap()).collect();
+    let linear: Vec<_> = parties
+        .iter()
+        .map(|party| regexes.iter().position(|regex| regex.is_match(party)))
+        .collect();
+    let linear_time = start.elapsed();
+
+    let start = Instant::now();
+    let set = RegexSet::new(&patterns).unwrap();
+    let one_pass: Vec<_> = parties
+        .iter()
+        .map(|party| set.matches(party).into_iter().next())
+        .collect();
+    let set_time = start.elapsed();
+
+    assert_eq!(linear, one_pass);
+    println!(
+        "{} parties against {} patterns: linear scan {:?}, RegexSet {:?}",
+        parties.len(),
+        patterns.len(),
+        linear_time,
+        set_time
+    );
+}
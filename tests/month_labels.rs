@@ -0,0 +1,23 @@
+use spenben::MonthYear;
+
+#[test]
+fn localized_month_names() {
+    let month = MonthYear {
+        month: 3,
+        year: 2024,
+    };
+    assert_eq!(month.to_string(), "2024 3");
+    assert_eq!(month.label("%B %Y", chrono::Locale::en_US), "March 2024");
+    assert_eq!(month.label("%B %Y", chrono::Locale::de_DE), "März 2024");
+    assert_eq!(month.label("%Y-%m", chrono::Locale::en_US), "2024-03");
+}
+
+#[test]
+fn invalid_formats_fall_back() {
+    let month = MonthYear {
+        month: 3,
+        year: 2024,
+    };
+    assert_eq!(month.label("%Y %Q", chrono::Locale::en_US), "2024 3");
+    assert_eq!(month.label("%H:%M", chrono::Locale::en_US), "2024 3");
+}
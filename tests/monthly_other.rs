@@ -0,0 +1,29 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+#[test]
+fn configured_other_group_is_merged() {
+    let config = toml::from_str(
+        r#"
+        monthly_other = true
+        [parties]
+        "shop" = "Shop"
+        "misc" = "Other"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, amount) in [("Shop", -50.0), ("Misc", -20.0), ("Cafe", -3.0)] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    assert_eq!(
+        groups.aggregate().unwrap().stats_monthly[0].1,
+        vec![("Shop".to_string(), -50.0), ("Other".to_string(), -23.0)]
+    );
+}
@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups, Record};
+
+#[test]
+fn zero_is_never_negative() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "shop" = "Shop"
+        "fee" = "Fees"
+        [settings."Fees"]
+        agg = "last"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    // An inverted zero amount and a refund netting the group to exactly zero
+    for (party, amount) in [("Fee", -0.0), ("Shop", -12.5), ("Shop", 12.5)] {
+        groups.push(Record {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party1: party,
+            party2: party,
+            description: "",
+            amount,
+            currency: "",
+            memo: "",
+            txn_id: None,
+            row: None,
+        });
+    }
+    let mut result = groups.aggregate().unwrap();
+    for (_, amount) in &result.stats_summary {
+        assert!(amount.is_sign_positive(), "{amount}");
+    }
+    result.stats_summary[0].1 = -0.001;
+    result.round(2);
+    assert!(result.stats_summary[0].1.is_sign_positive());
+}
@@ -0,0 +1,53 @@
+use chrono::NaiveDate;
+use spenben::{import, AnalysisOptions, Groups, ImportConfig, Record};
+
+const STATEMENT: &str =
+    "Date;Party;Memo;Amount\n2024-01-01;;\"Rent\r\npayment January\";-800\n2024-01-02;Shop;\"Order\n\n42\";-5\n";
+
+fn import_with(keep_newlines: bool) -> Vec<(String, String)> {
+    let config: ImportConfig = toml::from_str(&format!(
+        r#"
+        date_format = "%Y-%m-%d"
+        party_fallback = "description"
+        keep_newlines = {keep_newlines}
+        [map]
+        "Date" = "date"
+        "Party" = "party"
+        "Memo" = "description"
+        "Amount" = "amount"
+        "#
+    ))
+    .unwrap();
+    let mut records = vec![];
+    import(STATEMENT.as_bytes(), &config, true, |record| {
+        records.push((record.party1.to_string(), record.description.to_string()))
+    })
+    .unwrap();
+    records
+}
+
+#[test]
+fn lines_joined_within_fields() {
+    let records = import_with(false);
+    assert_eq!(records[0].1, "Rent payment January");
+    assert_eq!(records[1].1, "Order 42");
+    assert_eq!(import_with(true)[1].1, "Order\n\n42");
+
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "rent payment" = "Rent"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    groups.push(Record {
+        date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        party1: &records[0].0,
+        party2: &records[0].0,
+        description: &records[0].1,
+        amount: -800.0,
+        ..Default::default()
+    });
+    assert_eq!(groups.aggregate().unwrap().stats_summary[0].0, "Rent");
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, GroupConfig, Groups};
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 1, day).unwrap()
+}
+
+#[test]
+fn period_instead_of_record_dates() {
+    let mut groups = Groups::new(GroupConfig::default(), &AnalysisOptions::default()).unwrap();
+    groups.push(common::record(day(10), "shop", -10.0));
+    let mut restricted = groups.clone();
+    restricted.set_period(Some(day(1)), Some(day(31)));
+
+    let result = groups.aggregate().unwrap();
+    assert_eq!((result.start, result.end), (day(10), day(10)));
+    let result = restricted.aggregate().unwrap();
+    assert_eq!((result.start, result.end), (day(1), day(31)));
+}
+
+#[test]
+fn month_factor_counts_both_ends() {
+    let mut groups = Groups::new(GroupConfig::default(), &AnalysisOptions::default()).unwrap();
+    groups.push(common::record(day(10), "shop", -10.0));
+    let mut single = groups.clone();
+    single.set_period(Some(day(10)), Some(day(10)));
+    groups.set_period(Some(day(1)), Some(day(30)));
+
+    let result = groups.aggregate().unwrap();
+    assert_eq!(result.days(), 30);
+    assert_eq!(result.month_factor(30), Some(1.0));
+    assert_eq!(result.month_factor(31), None);
+    let result = single.aggregate().unwrap();
+    assert_eq!(result.days(), 1);
+    assert_eq!(result.month_factor(1), Some(30.0));
+    assert_eq!(result.month_factor(2), None);
+}
@@ -0,0 +1,33 @@
+use spenben::{read_report, MonthYear};
+use std::path::Path;
+
+/// Reports of older schema versions lack the fields added since, which are left empty
+#[test]
+fn first_schema_version() {
+    let file = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/report_v1.json");
+    let report = read_report(&file).unwrap();
+    assert_eq!(report.schema_version, 1);
+    assert_eq!(report.totals(), (5000.0, -60.75));
+    assert_eq!(
+        report.monthly_net().get(&MonthYear {
+            month: 2,
+            year: 2024
+        }),
+        Some(&2471.5)
+    );
+    assert!(report.budgets.is_empty());
+    assert!(report.metadata.is_none());
+}
+
+#[test]
+fn per_currency_report_is_rejected() {
+    let v1 = std::fs::read_to_string(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/report_v1.json"),
+    )
+    .unwrap();
+    let file = std::env::temp_dir().join(format!("spenben-currencies-{}.json", std::process::id()));
+    std::fs::write(&file, format!(r#"{{"EUR": {v1}, "USD": {v1}}}"#)).unwrap();
+    let error = read_report(&file).err().unwrap().to_string();
+    std::fs::remove_file(&file).unwrap();
+    assert!(error.contains("per currency"), "{error}");
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+#[test]
+fn totals_add_up_after_rounding() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "shop" = "Shop"
+        "cafe" = "Cafe"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, amount) in [("Shop", -10.4), ("Cafe", -3.4), ("Cafe", -0.2)] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let mut result = groups.aggregate().unwrap();
+    result.round(0);
+    assert_eq!(
+        result.stats_summary,
+        vec![("Shop".to_string(), -10.0), ("Cafe".to_string(), -4.0)]
+    );
+    assert_eq!(result.totals(), (0.0, -14.0));
+    assert_eq!(
+        result.monthly_net().into_values().collect::<Vec<_>>(),
+        vec![-14.0]
+    );
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{expense_ratio, savings_rate, AnalysisOptions, Groups};
+
+#[test]
+fn savings_rate_per_month() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "employer" = "Salary"
+        "shop" = "Shop"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (month, party, amount) in [
+        (1, "Employer", 2000.0),
+        (1, "Shop", -500.0),
+        (2, "Shop", -300.0),
+    ] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, month, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let result = groups.aggregate().unwrap();
+    assert_eq!(savings_rate(result.totals()), Some(0.6));
+    let rates: Vec<_> = result
+        .monthly_totals()
+        .into_values()
+        .map(savings_rate)
+        .collect();
+    assert_eq!(rates, vec![Some(0.75), None]);
+    let ratios: Vec<_> = result
+        .monthly_totals()
+        .into_values()
+        .map(expense_ratio)
+        .collect();
+    assert_eq!(ratios, vec![Some(0.25), None]);
+}
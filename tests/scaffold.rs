@@ -0,0 +1,16 @@
+use spenben::{scaffold_format, ImportConfig};
+
+#[test]
+fn scaffold_from_sample() {
+    let sample = std::fs::read(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/data/dkb/statement.csv"
+    ))
+    .unwrap();
+    let format = scaffold_format(&sample).unwrap();
+    let config: ImportConfig = toml::from_str(&format).unwrap();
+    assert_eq!(config.skip, Some(1));
+    let mut fields: Vec<_> = config.map.values().map(String::as_str).collect();
+    fields.sort();
+    assert_eq!(fields, ["amount", "date", "description", "party"]);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{Dedup, Record};
+
+fn record(day: u32, amount: f64) -> Record<'static> {
+    common::record(
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+        "shop",
+        amount,
+    )
+}
+
+#[test]
+fn only_new_records_after_saving() {
+    let file = std::env::temp_dir().join(format!("spenben-seen-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&file);
+
+    // Everything is new on the first run
+    let mut seen = Dedup::load(&file).unwrap();
+    assert!(!seen.is_duplicate(&record(1, -10.0)));
+    assert!(!seen.is_duplicate(&record(1, -10.0)));
+    seen.save(&file).unwrap();
+
+    let mut seen = Dedup::load(&file).unwrap();
+    assert!(seen.is_duplicate(&record(1, -10.0)));
+    assert!(seen.is_duplicate(&record(1, -10.0)));
+    // A third identical record is new
+    assert!(!seen.is_duplicate(&record(1, -10.0)));
+    assert!(!seen.is_duplicate(&record(2, -10.0)));
+    assert_eq!(seen.duplicates, 2);
+    std::fs::remove_file(&file).unwrap();
+}
+
+#[test]
+fn amounts_within_tolerance() {
+    let mut dedup = Dedup::default().with_tolerance(1);
+    assert!(!dedup.is_duplicate(&record(1, -12.335)));
+    assert!(!dedup.is_duplicate(&record(1, -20.0)));
+    dedup.finish_file();
+    assert!(dedup.is_duplicate(&record(1, -12.334)));
+    assert!(dedup.is_duplicate(&record(1, -20.01)));
+    // Only as often as seen before, and not beyond the tolerance
+    assert!(!dedup.is_duplicate(&record(1, -12.33)));
+    assert!(!dedup.is_duplicate(&record(1, -20.02)));
+    assert_eq!(dedup.duplicates, 2);
+}
@@ -0,0 +1,74 @@
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups, Record};
+
+fn groups_of(sources: &str) -> Vec<String> {
+    let config = toml::from_str(&format!(
+        r#"
+        {sources}
+        [parties]
+        "paypal" = "PayPal"
+        "netflix" = "Streaming"
+        "#
+    ))
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, description, amount) in [
+        ("PayPal Europe", "Netflix subscription", -10.0),
+        ("Corner Shop", "Netflix gift card", -25.0),
+        ("Corner Shop", "Bread", -3.0),
+    ] {
+        groups.push(Record {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party1: party,
+            party2: party,
+            description,
+            amount,
+            ..Default::default()
+        });
+    }
+    let mut groups: Vec<_> = groups
+        .aggregate()
+        .unwrap()
+        .stats_summary
+        .into_iter()
+        .map(|(group, _)| group)
+        .collect();
+    groups.sort();
+    groups
+}
+
+#[test]
+fn rules_matched_against_sources_in_order() {
+    assert_eq!(groups_of(""), ["PayPal", "corner shop"]);
+    assert_eq!(
+        groups_of(r#"sources = ["party", "description"]"#),
+        ["PayPal", "Streaming", "corner shop"]
+    );
+    assert_eq!(
+        groups_of(r#"sources = ["description", "party"]"#),
+        ["Streaming", "corner shop"]
+    );
+}
+
+#[test]
+fn memo_as_source() {
+    let config = toml::from_str(
+        r#"
+        sources = ["party", "memo"]
+        [parties]
+        "invoice 42" = "Project"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    groups.push(Record {
+        date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        party1: "Client",
+        party2: "Me",
+        description: "Payment",
+        memo: "Invoice 42, thanks",
+        amount: 500.0,
+        ..Default::default()
+    });
+    assert_eq!(groups.aggregate().unwrap().stats_summary[0].0, "Project");
+}
@@ -0,0 +1,37 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+#[test]
+fn groups_split_by_direction() {
+    let config = toml::from_str(
+        r#"
+        split_by_direction = true
+        [parties]
+        "shop" = "Shop"
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, amount) in [
+        ("Shop", -50.0),
+        ("Shop", -20.0),
+        ("Shop", 15.0),
+        ("Cafe", -3.0),
+    ] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    assert_eq!(
+        groups.aggregate().unwrap().stats_summary,
+        vec![
+            ("Shop (out)".to_string(), -70.0),
+            ("cafe (out)".to_string(), -3.0),
+            ("Shop (in)".to_string(), 15.0),
+        ]
+    );
+}
@@ -0,0 +1,37 @@
+use spenben::{import, ImportConfig};
+
+fn strict_import_error(map: &str) -> String {
+    let config: ImportConfig = toml::from_str(&format!(
+        r#"
+        date_format = "%Y-%m-%d"
+        [map]
+        "Date" = "date"
+        "Party" = "party"
+        {map}
+        "#
+    ))
+    .unwrap();
+    import(
+        "Date;Party;Amount\n2024-01-01;Shop;-1\n".as_bytes(),
+        &config,
+        true,
+        |_| (),
+    )
+    .unwrap_err()
+    .to_string()
+}
+
+#[test]
+fn column_taken_by_another_field() {
+    let error = strict_import_error(
+        r#""Am" = "description"
+        "Amount" = "amount""#,
+    );
+    assert!(error.contains("amount ('Amount')"), "{error}");
+}
+
+#[test]
+fn invalid_pattern() {
+    let error = strict_import_error(r#""Amount(" = "amount""#);
+    assert!(error.contains("amount ('Amount(')"), "{error}");
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups};
+
+#[test]
+fn parties_ranked_by_spending() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "shop" = "Shopping"
+        "#,
+    )
+    .unwrap();
+    let analysis = AnalysisOptions {
+        top_parties: 2,
+        ..Default::default()
+    };
+    let mut groups = Groups::new(config, &analysis).unwrap();
+    for (party, amount) in [
+        ("Shop A", -10.0),
+        ("Shop B", -50.0),
+        ("Shop A", -30.0),
+        ("Cafe", -5.0),
+        ("Employer", 1000.0),
+    ] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let ranking: Vec<_> = groups
+        .aggregate()
+        .unwrap()
+        .top_parties
+        .into_iter()
+        .map(|party| (party.party, party.count, party.amount))
+        .collect();
+    assert_eq!(
+        ranking,
+        vec![
+            ("Shop B".to_string(), 1, -50.0),
+            ("Shop A".to_string(), 2, -40.0)
+        ]
+    );
+}
@@ -0,0 +1,73 @@
+mod common;
+
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, GroupConfig, Groups};
+
+#[test]
+fn keeps_the_largest_records_per_month() {
+    let analysis = AnalysisOptions {
+        top_transactions: 2,
+        ..Default::default()
+    };
+    let mut groups = Groups::new(GroupConfig::default(), &analysis).unwrap();
+    let records = [
+        (1, 5, "a", -10.0),
+        (1, 6, "b", -300.0),
+        (1, 7, "c", 50.0),
+        (1, 8, "d", -20.0),
+        (2, 1, "e", -1.0),
+    ];
+    for (month, day, party, amount) in records {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, month, day).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let top: Vec<Vec<_>> = groups
+        .aggregate()
+        .unwrap()
+        .top_transactions
+        .into_iter()
+        .map(|(_, transactions)| {
+            transactions
+                .into_iter()
+                .map(|transaction| (transaction.party, transaction.amount))
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        top,
+        vec![
+            vec![("b".to_string(), -300.0), ("c".to_string(), 50.0)],
+            vec![("e".to_string(), -1.0)],
+        ]
+    );
+}
+
+#[test]
+fn large_records_without_top_transactions() {
+    let analysis = AnalysisOptions {
+        highlight_over: Some(100.0),
+        ..Default::default()
+    };
+    let mut groups = Groups::new(GroupConfig::default(), &analysis).unwrap();
+    for (party, amount) in [("a", -10.0), ("b", -300.0), ("c", 150.0), ("d", -100.0)] {
+        groups.push(common::record(
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party,
+            amount,
+        ));
+    }
+    let result = groups.aggregate().unwrap();
+    assert!(result.top_transactions.is_empty());
+    let large: Vec<_> = result
+        .large_transactions
+        .into_iter()
+        .map(|transaction| (transaction.party, transaction.amount))
+        .collect();
+    assert_eq!(
+        large,
+        vec![("b".to_string(), -300.0), ("c".to_string(), 150.0)]
+    );
+}
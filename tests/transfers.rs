@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use spenben::{match_transfers, OwnedRecord};
+
+fn record(day: u32, amount: f64) -> OwnedRecord {
+    OwnedRecord {
+        date: NaiveDate::from_ymd_opt(2024, 1, day).unwrap(),
+        party1: "me".to_string(),
+        party2: "me".to_string(),
+        amount,
+        currency: "EUR".to_string(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn pairs_opposite_amounts_on_the_same_day_across_accounts() {
+    let checking = vec![record(1, -100.0), record(2, -20.0), record(3, -50.0)];
+    let savings = vec![record(1, 100.0), record(2, 19.99), record(4, 50.0)];
+    let matched = match_transfers(&[checking, savings]);
+    assert_eq!(
+        matched,
+        vec![vec![true, false, false], vec![true, false, false]]
+    );
+}
+
+#[test]
+fn ignores_records_within_one_account() {
+    let checking = vec![record(1, -100.0), record(1, 100.0)];
+    assert_eq!(match_transfers(&[checking]), vec![vec![false, false]]);
+}
+
+#[test]
+fn pairs_each_record_once() {
+    let checking = vec![record(1, -100.0), record(1, -100.0)];
+    let savings = vec![record(1, 100.0)];
+    let matched = match_transfers(&[checking, savings]);
+    assert_eq!(matched, vec![vec![true, false], vec![true]]);
+}
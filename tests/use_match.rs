@@ -0,0 +1,41 @@
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups, Record};
+
+#[test]
+fn group_named_after_match() {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "paypal \\*\\w+" = { group = "PayPal", use_match = true }
+        "shop" = { group = "Shopping" }
+        "#,
+    )
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    for (party, amount) in [
+        ("PayPal *Spotify", -10.0),
+        ("PayPal *Netflix", -12.0),
+        ("PayPal *Spotify", -10.0),
+        ("Corner Shop", -3.0),
+    ] {
+        groups.push(Record {
+            date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            party1: party,
+            party2: party,
+            description: "",
+            amount,
+            currency: "",
+            memo: "",
+            txn_id: None,
+            row: None,
+        });
+    }
+    assert_eq!(
+        groups.aggregate().unwrap().stats_summary,
+        vec![
+            ("paypal *spotify".to_string(), -20.0),
+            ("paypal *netflix".to_string(), -12.0),
+            ("Shopping".to_string(), -3.0),
+        ]
+    );
+}
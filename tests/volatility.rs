@@ -0,0 +1,54 @@
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups, Record};
+
+fn volatility(active_months: bool) -> Vec<(String, f64)> {
+    let config = toml::from_str(
+        r#"
+        [parties]
+        "rent" = "Rent"
+        "airline" = "Travel"
+        "#,
+    )
+    .unwrap();
+    let analysis = AnalysisOptions {
+        volatility_active_months: active_months,
+        ..Default::default()
+    };
+    let mut groups = Groups::new(config, &analysis).unwrap();
+    for (month, party, amount) in [
+        (1, "Rent", -800.0),
+        (2, "Rent", -800.0),
+        (2, "Airline", -300.0),
+        (3, "Rent", -800.0),
+        (4, "Rent", -800.0),
+        (4, "Airline", -500.0),
+    ] {
+        groups.push(Record {
+            date: NaiveDate::from_ymd_opt(2024, month, 1).unwrap(),
+            party1: party,
+            party2: party,
+            description: "",
+            amount,
+            currency: "",
+            memo: "",
+            txn_id: None,
+            row: None,
+        });
+    }
+    groups.aggregate().unwrap().volatility
+}
+
+#[test]
+fn zero_months_count_by_default() {
+    let volatility = volatility(false);
+    assert_eq!(volatility[0], ("Rent".to_string(), 0.0));
+    // Months 0, -300, 0, -500 around a mean of -200
+    assert_eq!(volatility[1].0, "Travel");
+    assert!((volatility[1].1 - 212.132_034_355_964).abs() < 1e-9);
+}
+
+#[test]
+fn only_active_months() {
+    let volatility = volatility(true);
+    assert_eq!(volatility[1], ("Travel".to_string(), 100.0));
+}
@@ -0,0 +1,57 @@
+use chrono::NaiveDate;
+use spenben::{AnalysisOptions, Groups, Record};
+
+fn group_of_zero_amount(zero_amounts: &str) -> String {
+    let config = toml::from_str(&format!(
+        r#"
+        zero_amounts = "{zero_amounts}"
+        [parties]
+        "bank" = {{ group = "Fees", sign = "negative" }}
+        "me" = "Me"
+        "#
+    ))
+    .unwrap();
+    let mut groups = Groups::new(config, &AnalysisOptions::default()).unwrap();
+    groups.push(Record {
+        date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        party1: "Me",
+        party2: "Bank",
+        description: "Adjustment",
+        amount: 0.0,
+        ..Default::default()
+    });
+    groups.aggregate().unwrap().stats_summary[0].0.clone()
+}
+
+#[test]
+fn zero_amounts_as_income_or_expense() {
+    assert_eq!(group_of_zero_amount("income"), "Me");
+    assert_eq!(group_of_zero_amount("expense"), "Fees");
+}
+
+#[test]
+fn zero_amounts_in_histogram() {
+    let config = toml::from_str(
+        r#"
+        zero_amounts = "expense"
+        [parties]
+        "#,
+    )
+    .unwrap();
+    let analysis = AnalysisOptions {
+        histogram: true,
+        bands: vec![],
+        ..Default::default()
+    };
+    let mut groups = Groups::new(config, &analysis).unwrap();
+    groups.push(Record {
+        date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        party1: "Me",
+        party2: "Bank",
+        description: "Adjustment",
+        amount: 0.0,
+        ..Default::default()
+    });
+    let band = &groups.aggregate().unwrap().histogram[0];
+    assert_eq!((band.income_count, band.expense_count), (0, 1));
+}